@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Local, NaiveDate};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use local_timesheet_lib::index::EntryIndex;
+use local_timesheet_lib::TimeSheetEntry;
+
+/// Ten entries a day over ten years.
+fn generate_entries() -> Vec<TimeSheetEntry> {
+    let now = Local::now();
+    (0..365 * 10 * 10)
+        .map(|i| {
+            let start_time = now - Duration::minutes(i * 144);
+            TimeSheetEntry {
+                description: format!("Entry {i}"),
+                start_time,
+                end_time: Some(start_time + Duration::minutes(45)),
+                tags: vec!["bench".to_string()],
+                properties: HashMap::new(),
+            }
+        })
+        .collect()
+}
+
+fn bench_date_lookups(c: &mut Criterion) {
+    let entries = generate_entries();
+    let index = EntryIndex::new(entries.clone());
+    let date: NaiveDate = (Local::now() - Duration::days(5 * 365)).date_naive();
+    let sunday = date + Duration::days(6);
+
+    c.bench_function("10 years, day lookup, linear scan", |b| {
+        b.iter(|| {
+            entries
+                .iter()
+                .filter(|e| e.start_time.date_naive() == black_box(date))
+                .count()
+        })
+    });
+    c.bench_function("10 years, day lookup, date index", |b| {
+        b.iter(|| index.on_date(black_box(date)).count())
+    });
+    c.bench_function("10 years, week lookup, date index", |b| {
+        b.iter(|| index.in_range(black_box(date)..=black_box(sunday)).count())
+    });
+    c.bench_function("10 years, building the index", |b| {
+        b.iter(|| EntryIndex::new(black_box(entries.clone())))
+    });
+}
+
+criterion_group!(benches, bench_date_lookups);
+criterion_main!(benches);
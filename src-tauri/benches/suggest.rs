@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Local};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use local_timesheet_lib::suggest::rank_suggestions;
+use local_timesheet_lib::TimeSheetEntry;
+
+const WORDS: [&str; 12] = [
+    "standup", "review", "meeting", "project", "planning", "support", "email", "deploy",
+    "refactor", "interview", "design", "lunch",
+];
+
+/// 100k entries spread over roughly 4 years, with ~2000 distinct descriptions.
+fn generate_entries(count: usize) -> Vec<TimeSheetEntry> {
+    let now = Local::now();
+    (0..count)
+        .map(|i| {
+            let start_time = now - Duration::minutes(((count - i) * 20) as i64);
+            let description = format!(
+                "{} {} {}",
+                WORDS[i % WORDS.len()],
+                WORDS[(i / 7) % WORDS.len()],
+                i % 17
+            );
+            let mut properties = HashMap::new();
+            if i % 3 == 0 {
+                properties.insert("jira".to_string(), format!("PROJ-{}", i % 500));
+            }
+            TimeSheetEntry {
+                description,
+                start_time,
+                end_time: Some(start_time + Duration::minutes(15)),
+                tags: vec![WORDS[(i / 3) % WORDS.len()].to_string()],
+                properties,
+            }
+        })
+        .collect()
+}
+
+fn bench_suggestions(c: &mut Criterion) {
+    let entries = generate_entries(100_000);
+    let now = Local::now();
+
+    for partial in ["s", "rev", "stndp", "proj-12"] {
+        c.bench_function(&format!("rank_suggestions 100k \"{partial}\""), |b| {
+            b.iter(|| rank_suggestions(black_box(&entries), black_box(partial), now, 5))
+        });
+    }
+}
+
+criterion_group!(benches, bench_suggestions);
+criterion_main!(benches);
@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Timelike};
+use serde::Serialize;
+
+use crate::cache::EntryCache;
+use crate::{read_timesheet, timesheet_sidecar_path, write_entries, TimeSheetEntry};
+
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+
+/// Which snapshots survive rotation, configured from the environment.
+#[derive(Debug, Clone)]
+struct RotationPolicy {
+    /// `BACKUP_RECENT_COUNT`, latest snapshots always kept whatever their age.
+    recent_count: usize,
+    /// `BACKUP_HOURLY_HOURS`, how far back one snapshot per hour is kept.
+    hourly_for: Duration,
+    /// `BACKUP_DAILY_DAYS`, how far back one snapshot per day is kept.
+    daily_for: Duration,
+    /// `BACKUP_MAX_COUNT`, hard limit on the number of snapshots.
+    max_count: usize,
+}
+
+impl RotationPolicy {
+    fn from_env() -> Self {
+        fn var(name: &str, default: i64) -> i64 {
+            std::env::var(name)
+                .ok()
+                .map(|v| {
+                    v.parse()
+                        .unwrap_or_else(|_| panic!("{name} should be a number"))
+                })
+                .unwrap_or(default)
+        }
+
+        Self {
+            recent_count: var("BACKUP_RECENT_COUNT", 10) as usize,
+            hourly_for: Duration::hours(var("BACKUP_HOURLY_HOURS", 24)),
+            daily_for: Duration::days(var("BACKUP_DAILY_DAYS", 30)),
+            max_count: var("BACKUP_MAX_COUNT", 100) as usize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    /// Epoch milliseconds
+    pub created_at: i64,
+    pub size: u64,
+}
+
+/// Changes from a backup to the current timesheet, matching entries by description and start time.
+#[derive(Debug, Default, Serialize)]
+pub struct BackupDiff {
+    pub added: Vec<TimeSheetEntry>,
+    pub removed: Vec<TimeSheetEntry>,
+    /// Pairs of (backup, current) entries
+    pub changed: Vec<(TimeSheetEntry, TimeSheetEntry)>,
+}
+
+/// `BACKUP_DIR`, or `<timesheet>.backups` next to `TIMESHEET_PATH`.
+pub fn backup_dir() -> PathBuf {
+    match std::env::var("BACKUP_DIR") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("backups"),
+    }
+}
+
+pub(crate) fn timesheet_stem() -> String {
+    PathBuf::from(std::env::var("TIMESHEET_PATH").unwrap())
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "timesheet".to_string())
+}
+
+fn parse_backup_time(name: &str, stem: &str) -> Option<DateTime<Local>> {
+    let timestamp = name
+        .strip_prefix(stem)?
+        .strip_prefix('-')?
+        .strip_suffix(".csv")?;
+    let naive = NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// Snapshots of the timesheet, newest first.
+pub(crate) fn backups() -> Vec<(PathBuf, DateTime<Local>)> {
+    let dir = backup_dir();
+    if !std::fs::exists(&dir).unwrap() {
+        return Vec::new();
+    }
+
+    let stem = timesheet_stem();
+    let mut backups: Vec<(PathBuf, DateTime<Local>)> = std::fs::read_dir(&dir)
+        .unwrap()
+        .filter_map(|e| {
+            let path = e.unwrap().path();
+            let created_at = parse_backup_time(&path.file_name()?.to_string_lossy(), &stem)?;
+            Some((path, created_at))
+        })
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.1));
+
+    backups
+}
+
+fn find_backup(name: &str) -> Result<PathBuf, String> {
+    backups()
+        .into_iter()
+        .map(|(path, _)| path)
+        .find(|path| path.file_name().is_some_and(|n| n == name))
+        .ok_or_else(|| format!("No backup named {name}"))
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    let (Ok(a_meta), Ok(b_meta)) = (std::fs::metadata(a), std::fs::metadata(b)) else {
+        return false;
+    };
+    a_meta.len() == b_meta.len() && std::fs::read(a).ok() == std::fs::read(b).ok()
+}
+
+/// Copies the current timesheet into the backup directory before it gets overwritten, then rotates.
+///
+/// Nothing is copied if the timesheet is identical to the latest snapshot.
+pub fn snapshot() {
+    let timesheet_path = PathBuf::from(std::env::var("TIMESHEET_PATH").unwrap());
+    if !std::fs::exists(&timesheet_path).unwrap() {
+        return;
+    }
+
+    let existing = backups();
+    if existing
+        .first()
+        .is_some_and(|(latest, _)| same_content(latest, &timesheet_path))
+    {
+        return;
+    }
+
+    let dir = backup_dir();
+    std::fs::create_dir_all(&dir).unwrap();
+    let now = Local::now();
+    let backup_path = dir.join(format!(
+        "{}-{}.csv",
+        timesheet_stem(),
+        now.format(BACKUP_TIMESTAMP_FORMAT)
+    ));
+    std::fs::copy(&timesheet_path, &backup_path).unwrap();
+
+    let mut snapshots = vec![(backup_path, now)];
+    snapshots.extend(existing);
+    for path in expired(&snapshots, now, &RotationPolicy::from_env()) {
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("[WARN] Failed to remove old backup {}: {e}", path.display());
+        }
+    }
+}
+
+/// Snapshots to delete, given snapshots sorted newest first.
+///
+/// Keeps the most recent ones, then the newest snapshot of each hour and of each day
+/// within their respective windows, up to the maximum count.
+fn expired(
+    snapshots: &[(PathBuf, DateTime<Local>)],
+    now: DateTime<Local>,
+    policy: &RotationPolicy,
+) -> Vec<PathBuf> {
+    let mut seen_hours = HashSet::new();
+    let mut seen_days = HashSet::new();
+    let mut kept = 0;
+    let mut expired = Vec::new();
+
+    for (i, (path, created_at)) in snapshots.iter().enumerate() {
+        let age = now - *created_at;
+        let hour = created_at
+            .with_minute(0)
+            .and_then(|h| h.with_second(0))
+            .map(|h| h.naive_local());
+        let day = created_at.date_naive();
+
+        let first_of_hour = seen_hours.insert(hour);
+        let first_of_day = seen_days.insert(day);
+        let keep = i < policy.recent_count
+            || (age <= policy.hourly_for && first_of_hour)
+            || (age <= policy.daily_for && first_of_day);
+
+        if keep && kept < policy.max_count {
+            kept += 1;
+        } else {
+            expired.push(path.clone());
+        }
+    }
+
+    expired
+}
+
+#[tauri::command]
+pub fn list_backups() -> Vec<BackupInfo> {
+    backups()
+        .into_iter()
+        .map(|(path, created_at)| BackupInfo {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            created_at: created_at.timestamp_millis(),
+            size: std::fs::metadata(&path)
+                .map(|m| m.len())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn diff_backup(name: String) -> Result<BackupDiff, String> {
+    let backup_entries = read_timesheet(find_backup(&name)?);
+    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
+    let current_entries = if std::fs::exists(&timesheet_path).unwrap() {
+        read_timesheet(timesheet_path)
+    } else {
+        Vec::new()
+    };
+
+    Ok(diff(backup_entries, current_entries))
+}
+
+fn diff(backup_entries: Vec<TimeSheetEntry>, current_entries: Vec<TimeSheetEntry>) -> BackupDiff {
+    let key = |e: &TimeSheetEntry| (e.description.clone(), e.start_time);
+    let mut backup_map: HashMap<_, TimeSheetEntry> =
+        backup_entries.into_iter().map(|e| (key(&e), e)).collect();
+
+    let mut diff = BackupDiff::default();
+    for current in current_entries {
+        match backup_map.remove(&key(&current)) {
+            None => diff.added.push(current),
+            Some(old) => {
+                if old.end_time != current.end_time
+                    || old.tags != current.tags
+                    || old.properties != current.properties
+                {
+                    diff.changed.push((old, current));
+                }
+            }
+        }
+    }
+    diff.removed = backup_map.into_values().collect();
+    diff.removed.sort_by_key(|e| e.start_time);
+
+    diff
+}
+
+/// Replaces the timesheet with the backup's content, the current state being backed up first.
+#[tauri::command]
+pub fn restore_backup(cache: &EntryCache, name: String) -> Result<(), String> {
+    let entries = read_timesheet(find_backup(&name)?);
+    write_entries(cache, &entries);
+
+    println!("[INFO] Restored timesheet from backup {name}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_entry, get_entries, lock_test_env};
+
+    #[test]
+    fn test_rotation() {
+        let now = Local.with_ymd_and_hms(2025, 6, 10, 12, 0, 0).unwrap();
+        let policy = RotationPolicy {
+            recent_count: 2,
+            hourly_for: Duration::hours(24),
+            daily_for: Duration::days(30),
+            max_count: 100,
+        };
+        let snapshot = |minutes_ago: i64| {
+            (
+                PathBuf::from(format!("{minutes_ago}")),
+                now - Duration::minutes(minutes_ago),
+            )
+        };
+        let snapshots = vec![
+            snapshot(1),
+            snapshot(2),
+            snapshot(3),            // same hour as the two recent ones
+            snapshot(70),           // newest of its hour
+            snapshot(80),           // same hour
+            snapshot(60 * 30),      // newest of its day
+            snapshot(60 * 31),      // same day
+            snapshot(60 * 24 * 40), // past the daily window
+        ];
+
+        let expired: Vec<String> = expired(&snapshots, now, &policy)
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(expired, vec!["3", "80", "1860", "57600"]);
+
+        let capped = RotationPolicy {
+            max_count: 3,
+            ..policy
+        };
+        assert_eq!(super::expired(&snapshots, now, &capped).len(), 5);
+    }
+
+    #[test]
+    fn test_backup_diff_and_restore() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("BACKUP_DIR");
+
+        let now = Local::now();
+        let entry = |description: &str, start_time: DateTime<Local>| TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: None,
+            tags: vec!["test".to_string()],
+            properties: Default::default(),
+        };
+        assert!(add_entry(&cache, entry("First", now)));
+        assert!(
+            list_backups().is_empty(),
+            "Nothing to back up before the first write"
+        );
+        assert!(add_entry(
+            &cache,
+            entry("Second", now + Duration::milliseconds(1))
+        ));
+
+        let backups = list_backups();
+        assert_eq!(backups.len(), 1);
+        let diff = diff_backup(backups[0].name.clone()).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].description, "Second");
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+
+        restore_backup(&cache, backups[0].name.clone()).unwrap();
+        assert_eq!(get_entries().len(), 1);
+        // The state before restoring was backed up too
+        assert_eq!(list_backups().len(), 2);
+        assert!(restore_backup(&cache, "missing.csv".to_string()).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::rounding::rounded_durations;
+use crate::{parse_date, timesheet_sidecar_path, TimeSheetEntry};
+
+/// Hourly rate of a project or tag from a date on, until a later rate for the same one takes over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rate {
+    /// Matches the entry's `project` property
+    pub project: Option<String>,
+    pub tag: Option<String>,
+    pub hourly_rate: f64,
+    pub effective_from: NaiveDate,
+}
+
+/// Billable hours at one rate, one row of the invoice.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvoiceLine {
+    pub project: String,
+    /// `None` for billable hours no rate applies to
+    pub hourly_rate: Option<f64>,
+    pub hours: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClientSummary {
+    pub client: String,
+    pub billable_hours: f64,
+    pub non_billable_hours: f64,
+    pub amount: f64,
+    pub lines: Vec<InvoiceLine>,
+}
+
+/// `RATES_PATH`, or `<timesheet>.rates.json` next to `TIMESHEET_PATH`.
+fn rates_path() -> PathBuf {
+    match std::env::var("RATES_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("rates.json"),
+    }
+}
+
+pub fn load_rates() -> Vec<Rate> {
+    let path = rates_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Vec::new();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Rates file should be valid json")
+}
+
+/// Rate of the entry on its day, project rates taking precedence over tag rates.
+pub fn rate_for(entry: &TimeSheetEntry, rates: &[Rate]) -> Option<f64> {
+    let date = entry.start_time.date_naive();
+    let project = entry.properties.get("project");
+    let latest = |matches: &dyn Fn(&Rate) -> bool| {
+        rates
+            .iter()
+            .filter(|r| r.effective_from <= date && matches(r))
+            .max_by_key(|r| r.effective_from)
+            .map(|r| r.hourly_rate)
+    };
+
+    latest(&|r| r.project.is_some() && r.project.as_ref() == project).or_else(|| {
+        latest(&|r| r.project.is_none() && r.tag.as_ref().is_some_and(|t| entry.tags.contains(t)))
+    })
+}
+
+/// The `billable` property, or whether a rate applies when it isn't set.
+pub fn is_billable(entry: &TimeSheetEntry, rates: &[Rate]) -> bool {
+    match entry.properties.get("billable") {
+        Some(billable) => billable == "true",
+        None => rate_for(entry, rates).is_some(),
+    }
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[derive(Default)]
+struct ClientTotals {
+    non_billable_hours: f64,
+    /// Billable hours by project and rate, the rate as cents to group by it
+    lines: BTreeMap<(String, Option<i64>), f64>,
+}
+
+/// Billable hours and amounts per client, with durations rounded per `ROUNDING_MINUTES`.
+pub fn summarize<'a>(
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+    rates: &[Rate],
+) -> Vec<ClientSummary> {
+    let mut clients: BTreeMap<String, ClientTotals> = BTreeMap::new();
+    for (entry, duration) in rounded_durations(entries) {
+        let hours = duration.num_milliseconds() as f64 / 3_600_000.0;
+        let client = entry.properties.get("client").cloned();
+        let totals = clients
+            .entry(client.unwrap_or_else(|| "(no client)".to_string()))
+            .or_default();
+        if !is_billable(entry, rates) {
+            totals.non_billable_hours += hours;
+            continue;
+        }
+        let project = entry.properties.get("project").cloned().unwrap_or_default();
+        let rate = rate_for(entry, rates).map(|r| (r * 100.0).round() as i64);
+        *totals.lines.entry((project, rate)).or_default() += hours;
+    }
+
+    clients
+        .into_iter()
+        .map(|(client, totals)| {
+            let lines: Vec<InvoiceLine> = totals
+                .lines
+                .into_iter()
+                .map(|((project, rate), hours)| {
+                    let hourly_rate = rate.map(|cents| cents as f64 / 100.0);
+                    InvoiceLine {
+                        project,
+                        hourly_rate,
+                        hours,
+                        amount: round_cents(hours * hourly_rate.unwrap_or_default()),
+                    }
+                })
+                .collect();
+            ClientSummary {
+                client,
+                billable_hours: lines.iter().map(|l| l.hours).sum(),
+                non_billable_hours: totals.non_billable_hours,
+                amount: round_cents(lines.iter().map(|l| l.amount).sum()),
+                lines,
+            }
+        })
+        .collect()
+}
+
+/// Writes one row per client, project and rate, followed by each client's total.
+pub fn write_billing_csv(path: impl AsRef<Path>, summaries: &[ClientSummary]) {
+    let mut writer = csv::Writer::from_path(path).unwrap();
+    writer
+        .write_record(["Client", "Project", "Rate", "Hours", "Amount"])
+        .unwrap();
+    for summary in summaries.iter().filter(|s| !s.lines.is_empty()) {
+        for line in summary.lines.iter() {
+            writer
+                .write_record([
+                    summary.client.clone(),
+                    line.project.clone(),
+                    line.hourly_rate
+                        .map(|r| format!("{r:.2}"))
+                        .unwrap_or_default(),
+                    format!("{:.2}", line.hours),
+                    format!("{:.2}", line.amount),
+                ])
+                .unwrap();
+        }
+        writer
+            .write_record([
+                summary.client.clone(),
+                "Total".to_string(),
+                String::new(),
+                format!("{:.2}", summary.billable_hours),
+                format!("{:.2}", summary.amount),
+            ])
+            .unwrap();
+    }
+    writer.flush().unwrap();
+}
+
+#[tauri::command]
+pub fn get_rates() -> Vec<Rate> {
+    load_rates()
+}
+
+#[tauri::command]
+pub fn save_rates(rates: Vec<Rate>) -> bool {
+    let file = std::fs::File::create(rates_path()).unwrap();
+    serde_json::to_writer_pretty(file, &rates).unwrap();
+
+    true
+}
+
+#[tauri::command]
+pub fn get_billing_summary(
+    cache: &EntryCache,
+    from: &str,
+    to: &str,
+) -> Result<Vec<ClientSummary>, String> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+
+    let entries = cache.in_range(from..=to);
+    Ok(summarize(&entries, &load_rates()))
+}
+
+#[tauri::command]
+pub fn export_billing(
+    cache: &EntryCache,
+    path: String,
+    from: &str,
+    to: &str,
+) -> Result<(), String> {
+    let summaries = get_billing_summary(cache, from, to)?;
+    write_billing_csv(&path, &summaries);
+    println!("[INFO] Wrote billing summary from {from} to {to} to {path}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local, TimeZone};
+
+    #[test]
+    fn test_summary() {
+        let entry = |day, minutes, tags: &[&str], properties: &[(&str, &str)]| {
+            let start_time = Local.with_ymd_and_hms(2025, 6, day, 9, 0, 0).unwrap();
+            TimeSheetEntry {
+                description: "Work".to_string(),
+                start_time,
+                end_time: Some(start_time + Duration::minutes(minutes)),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                properties: properties
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            }
+        };
+        let date = |day| NaiveDate::from_ymd_opt(2025, 6, day).unwrap();
+        let rates = [
+            Rate {
+                project: Some("Website".to_string()),
+                tag: None,
+                hourly_rate: 100.0,
+                effective_from: date(1),
+            },
+            Rate {
+                project: Some("Website".to_string()),
+                tag: None,
+                hourly_rate: 120.0,
+                effective_from: date(15),
+            },
+            Rate {
+                project: None,
+                tag: Some("support".to_string()),
+                hourly_rate: 80.0,
+                effective_from: date(1),
+            },
+        ];
+        let acme = [("client", "Acme"), ("project", "Website")];
+        let entries = [
+            entry(10, 90, &["support"], &acme),
+            entry(16, 60, &[], &acme),
+            entry(
+                17,
+                30,
+                &[],
+                &[
+                    ("client", "Acme"),
+                    ("project", "Website"),
+                    ("billable", "false"),
+                ],
+            ),
+            entry(10, 45, &["support"], &[("client", "Globex")]),
+            entry(11, 60, &[], &[("client", "Globex"), ("billable", "true")]),
+            entry(12, 120, &["dev"], &[]),
+        ];
+
+        assert_eq!(
+            rate_for(&entries[0], &rates),
+            Some(100.0),
+            "Project over tag"
+        );
+        assert_eq!(rate_for(&entries[1], &rates), Some(120.0));
+        assert_eq!(rate_for(&entries[3], &rates), Some(80.0));
+        assert!(!is_billable(&entries[5], &rates));
+
+        let summaries = summarize(&entries, &rates);
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|s| s.client.as_str())
+                .collect::<Vec<_>>(),
+            ["(no client)", "Acme", "Globex"]
+        );
+        let acme = &summaries[1];
+        assert_eq!((acme.billable_hours, acme.non_billable_hours), (2.5, 0.5));
+        assert_eq!(acme.amount, 270.0);
+        assert_eq!(acme.lines.len(), 2);
+        assert_eq!(
+            summaries[2].lines[0].hourly_rate, None,
+            "Billable without a rate"
+        );
+        assert_eq!(summaries[2].amount, 60.0);
+        assert_eq!(summaries[0].non_billable_hours, 2.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("billing.csv");
+        write_billing_csv(&path, &summaries);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "Client,Project,Rate,Hours,Amount\n\
+             Acme,Website,100.00,1.50,150.00\n\
+             Acme,Website,120.00,1.00,120.00\n\
+             Acme,Total,,2.50,270.00\n\
+             Globex,,,1.00,0.00\n\
+             Globex,,80.00,0.75,60.00\n\
+             Globex,Total,,1.75,60.00\n"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::rounding::rounded_durations;
+use crate::{timesheet_sidecar_path, TimeSheetEntry};
+
+/// Event emitted to the frontend when a change pushes a budget past its warning threshold.
+pub const BUDGET_WARNING_EVENT: &str = "budget-warning";
+
+/// Days of entries the burn rate is computed over.
+const BURN_RATE_DAYS: u64 = 28;
+
+/// Hours budgeted for a project, a Jira issue, or a Jira issue within a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    /// Matches the entry's `project` property
+    pub project: Option<String>,
+    /// Matches the entry's `jira` property
+    pub jira: Option<String>,
+    pub hours: f64,
+    /// Entries before aren't counted
+    pub since: Option<NaiveDate>,
+    /// Share of the budget used from which a warning is sent, none when unset
+    pub warn_at_percent: Option<f64>,
+}
+
+impl Budget {
+    fn key(&self) -> String {
+        format!(
+            "{}/{}",
+            self.project.as_deref().unwrap_or_default(),
+            self.jira.as_deref().unwrap_or_default()
+        )
+    }
+
+    fn matches(&self, entry: &TimeSheetEntry) -> bool {
+        let property_matches = |key: &str, value: &Option<String>| {
+            value
+                .as_ref()
+                .is_none_or(|value| entry.properties.get(key) == Some(value))
+        };
+        (self.project.is_some() || self.jira.is_some())
+            && property_matches("project", &self.project)
+            && property_matches("jira", &self.jira)
+            && self
+                .since
+                .is_none_or(|since| entry.start_time.date_naive() >= since)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetStatus {
+    #[serde(flatten)]
+    pub budget: Budget,
+    pub consumed_hours: f64,
+    /// Negative once over budget
+    pub remaining_hours: f64,
+    pub percent_used: f64,
+    /// Average hours per day over the last weeks
+    pub burn_rate_hours_per_day: f64,
+    /// Day the budget runs out at the current burn rate, none when already over or not being worked on
+    pub projected_overrun_date: Option<NaiveDate>,
+}
+
+impl BudgetStatus {
+    fn is_past_threshold(&self) -> bool {
+        self.budget
+            .warn_at_percent
+            .is_some_and(|threshold| self.percent_used >= threshold)
+    }
+}
+
+/// `BUDGETS_PATH`, or `<timesheet>.budgets.json` next to `TIMESHEET_PATH`.
+fn budgets_path() -> PathBuf {
+    match std::env::var("BUDGETS_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("budgets.json"),
+    }
+}
+
+pub fn load_budgets() -> Vec<Budget> {
+    let path = budgets_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Vec::new();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Budgets file should be valid json")
+}
+
+/// Consumption of the budget as of `today`, with durations rounded per `ROUNDING_MINUTES`.
+pub fn status(budget: &Budget, entries: &[TimeSheetEntry], today: NaiveDate) -> BudgetStatus {
+    let consumed = rounded_durations(entries.iter().filter(|e| budget.matches(e)));
+    let hours = |(_, duration): &(&TimeSheetEntry, chrono::Duration)| {
+        duration.num_milliseconds() as f64 / 3_600_000.0
+    };
+    let consumed_hours: f64 = consumed.iter().map(hours).sum();
+    let remaining_hours = budget.hours - consumed_hours;
+
+    // Over the last weeks, or since the first entry when it's more recent
+    let window_start = today - Days::new(BURN_RATE_DAYS - 1);
+    let first_day = consumed
+        .iter()
+        .map(|(e, _)| e.start_time.date_naive())
+        .min()
+        .unwrap_or(today)
+        .max(window_start);
+    let days = (today - first_day).num_days() + 1;
+    let recent_hours: f64 = consumed
+        .iter()
+        .filter(|(e, _)| e.start_time.date_naive() >= first_day)
+        .map(hours)
+        .sum();
+    let burn_rate_hours_per_day = recent_hours / days as f64;
+
+    let projected_overrun_date = (remaining_hours > 0.0 && burn_rate_hours_per_day > 0.0)
+        .then(|| today + Days::new((remaining_hours / burn_rate_hours_per_day).ceil() as u64));
+
+    BudgetStatus {
+        budget: budget.clone(),
+        consumed_hours,
+        remaining_hours,
+        percent_used: if budget.hours > 0.0 {
+            consumed_hours / budget.hours * 100.0
+        } else {
+            0.0
+        },
+        burn_rate_hours_per_day,
+        projected_overrun_date,
+    }
+}
+
+/// Statuses past their threshold that weren't at the previous check, remembering the ones that are.
+fn crossed_thresholds(
+    previous: &mut HashSet<String>,
+    statuses: Vec<BudgetStatus>,
+) -> Vec<BudgetStatus> {
+    let past: Vec<BudgetStatus> = statuses
+        .into_iter()
+        .filter(BudgetStatus::is_past_threshold)
+        .collect();
+    let crossed = past
+        .iter()
+        .filter(|s| !previous.contains(&s.budget.key()))
+        .cloned()
+        .collect();
+    *previous = past.iter().map(|s| s.budget.key()).collect();
+
+    crossed
+}
+
+/// Budgets past their threshold when last checked, `None` until the first check.
+static PAST_THRESHOLD: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Budgets the entries changed since the last call pushed past their warning threshold.
+///
+/// The first call only takes note of the budgets already past it.
+pub fn check_thresholds(cache: &EntryCache) -> Vec<BudgetStatus> {
+    let budgets = load_budgets();
+    if budgets.iter().all(|b| b.warn_at_percent.is_none()) {
+        return Vec::new();
+    }
+    let statuses = get_budget_status(cache);
+
+    let mut previous = PAST_THRESHOLD.lock().unwrap();
+    match previous.as_mut() {
+        Some(previous) => crossed_thresholds(previous, statuses),
+        None => {
+            crossed_thresholds(previous.insert(HashSet::new()), statuses);
+            Vec::new()
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_budgets() -> Vec<Budget> {
+    load_budgets()
+}
+
+#[tauri::command]
+pub fn save_budgets(budgets: Vec<Budget>) -> bool {
+    let file = std::fs::File::create(budgets_path()).unwrap();
+    serde_json::to_writer_pretty(file, &budgets).unwrap();
+
+    true
+}
+
+#[tauri::command]
+pub fn get_budget_status(cache: &EntryCache) -> Vec<BudgetStatus> {
+    let today = Local::now().date_naive();
+    load_budgets()
+        .iter()
+        .map(|budget| {
+            // Narrowed down by the most specific property, `status` checking the rest
+            let entries = match (&budget.jira, &budget.project) {
+                (Some(jira), _) => cache.with_property("jira", jira),
+                (None, Some(project)) => cache.with_property("project", project),
+                (None, None) => Vec::new(),
+            };
+            status(budget, &entries, today)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use std::collections::HashMap;
+
+    fn entry(day: u32, hours: i64, project: &str, jira: &str) -> TimeSheetEntry {
+        let start_time = Local.with_ymd_and_hms(2025, 6, day, 9, 0, 0).unwrap();
+        TimeSheetEntry {
+            description: "Work".to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::hours(hours)),
+            tags: vec![],
+            properties: HashMap::from([
+                ("project".to_string(), project.to_string()),
+                ("jira".to_string(), jira.to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_status_and_warnings() {
+        let budget = Budget {
+            project: Some("Website".to_string()),
+            jira: None,
+            hours: 20.0,
+            since: None,
+            warn_at_percent: Some(80.0),
+        };
+        let mut entries = vec![
+            entry(2, 4, "Website", "WEB-1"),
+            entry(3, 4, "Website", "WEB-2"),
+            entry(3, 8, "Intranet", "INT-1"),
+            entry(5, 2, "Website", "WEB-1"),
+        ];
+        let today = NaiveDate::from_ymd_opt(2025, 6, 6).unwrap();
+
+        let status = status(&budget, &entries, today);
+        assert_eq!(
+            (status.consumed_hours, status.remaining_hours),
+            (10.0, 10.0)
+        );
+        assert_eq!(status.percent_used, 50.0);
+        // 10 hours over the 5 days since the 2nd
+        assert_eq!(status.burn_rate_hours_per_day, 2.0);
+        assert_eq!(
+            status.projected_overrun_date,
+            NaiveDate::from_ymd_opt(2025, 6, 11)
+        );
+
+        let issue = Budget {
+            project: None,
+            jira: Some("WEB-1".to_string()),
+            since: NaiveDate::from_ymd_opt(2025, 6, 4),
+            ..budget.clone()
+        };
+        assert_eq!(super::status(&issue, &entries, today).consumed_hours, 2.0);
+
+        let mut previous = HashSet::new();
+        let statuses = |entries: &[TimeSheetEntry]| vec![super::status(&budget, entries, today)];
+        assert!(crossed_thresholds(&mut previous, statuses(&entries)).is_empty());
+        entries.push(entry(6, 6, "Website", "WEB-3"));
+        let crossed = crossed_thresholds(&mut previous, statuses(&entries));
+        assert_eq!(crossed.len(), 1);
+        assert_eq!(crossed[0].percent_used, 80.0);
+        assert!(
+            crossed_thresholds(&mut previous, statuses(&entries)).is_empty(),
+            "Warned once"
+        );
+        entries.pop();
+        crossed_thresholds(&mut previous, statuses(&entries));
+        entries.push(entry(6, 7, "Website", "WEB-3"));
+        assert_eq!(
+            crossed_thresholds(&mut previous, statuses(&entries)).len(),
+            1,
+            "Warned again after going back under"
+        );
+    }
+}
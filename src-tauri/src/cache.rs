@@ -0,0 +1,232 @@
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use tauri::ipc::{CommandArg, CommandItem, InvokeError};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::index::EntryIndex;
+#[cfg(feature = "sqlite")]
+use crate::sqlite;
+use crate::{budgets, eventlog, get_entries, StorageMode, TimeSheetEntry};
+
+/// Event emitted to the frontend whenever the timesheet files changed, whoever changed them.
+pub const ENTRIES_CHANGED_EVENT: &str = "entries-changed";
+
+/// In-memory copy of what `get_entries()` reads from disk, indexed by date.
+///
+/// Only kept while a watcher is running, since nothing else tells us about edits made
+/// outside the app (Excel, OneDrive sync...). Without one, every read goes to disk,
+/// which is what the command line and tests get with their own `EntryCache::new()`.
+///
+/// The app's one lives in Tauri managed state.
+pub struct EntryCache {
+    entries: RwLock<Option<Arc<EntryIndex>>>,
+    watching: AtomicBool,
+}
+
+impl EntryCache {
+    pub const fn new() -> Self {
+        Self {
+            entries: RwLock::new(None),
+            watching: AtomicBool::new(false),
+        }
+    }
+
+    /// Entries from the cache, reading them from disk if needed.
+    pub fn entries(&self) -> Arc<EntryIndex> {
+        self.entries_with(get_entries)
+    }
+
+    /// Entries that started within the range of local dates, ordered by date.
+    pub fn in_range(&self, dates: RangeInclusive<NaiveDate>) -> Vec<TimeSheetEntry> {
+        #[cfg(feature = "sqlite")]
+        if let Some(mut entries) = from_database(|| sqlite::entries_on_dates(dates.clone())) {
+            entries.sort_by_key(|e| e.start_time.date_naive());
+            return entries;
+        }
+
+        self.entries().in_range(dates).cloned().collect()
+    }
+
+    /// Entries whose property has this value.
+    pub fn with_property(&self, key: &str, value: &str) -> Vec<TimeSheetEntry> {
+        #[cfg(feature = "sqlite")]
+        if let Some(entries) = from_database(|| sqlite::entries_with_property(key, value)) {
+            return entries;
+        }
+
+        self.entries()
+            .iter()
+            .filter(|e| e.properties.get(key).is_some_and(|v| v == value))
+            .cloned()
+            .collect()
+    }
+
+    fn entries_with(&self, load: impl FnOnce() -> Vec<TimeSheetEntry>) -> Arc<EntryIndex> {
+        if !self.watching.load(Ordering::Acquire) {
+            return Arc::new(load().into());
+        }
+
+        if let Some(entries) = self.entries.read().unwrap().as_ref() {
+            return entries.clone();
+        }
+
+        let mut cached = self.entries.write().unwrap();
+        cached.get_or_insert_with(|| Arc::new(load().into())).clone()
+    }
+
+    /// Drops the cached entries, for after the app itself wrote to the timesheet.
+    pub fn invalidate(&self) {
+        *self.entries.write().unwrap() = None;
+    }
+
+    fn set_watching(&self, watching: bool) {
+        self.watching.store(watching, Ordering::Release);
+        self.invalidate();
+    }
+}
+
+/// Runs the query against the database in sqlite mode, which indexes dates, tags and properties.
+///
+/// Entries from `TOGGL_SHEET_PATH` aren't in the database, they're only found through the cache.
+#[cfg(feature = "sqlite")]
+fn from_database(query: impl FnOnce() -> Vec<TimeSheetEntry>) -> Option<Vec<TimeSheetEntry>> {
+    let sqlite_only = StorageMode::from_env() == StorageMode::Sqlite
+        && std::env::var("TOGGL_SHEET_PATH").is_err();
+    sqlite_only.then(query)
+}
+
+impl Default for EntryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lets commands take `cache: &EntryCache` like the rest of the code, getting the managed one
+/// through [`State`], while the command line and tests pass their own.
+impl<'de, R: Runtime> CommandArg<'de, R> for &'de EntryCache {
+    fn from_command(command: CommandItem<'de, R>) -> Result<Self, InvokeError> {
+        let state: State<'de, EntryCache> = CommandArg::from_command(command)?;
+        Ok(state.inner())
+    }
+}
+
+/// Keeps the filesystem watcher alive as Tauri managed state.
+pub struct TimesheetWatcher(#[allow(dead_code)] Mutex<Debouncer<RecommendedWatcher>>);
+
+/// Files backing `get_entries()`, from `TIMESHEET_PATH` and `TOGGL_SHEET_PATH`,
+/// plus the event log directory or the database when using them.
+fn watched_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = ["TIMESHEET_PATH", "TOGGL_SHEET_PATH"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|path| std::path::absolute(path).unwrap())
+        .collect();
+    if StorageMode::from_env() == StorageMode::EventLog {
+        let dir = std::path::absolute(eventlog::event_log_dir()).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        paths.push(dir);
+    }
+    #[cfg(feature = "sqlite")]
+    if StorageMode::from_env() == StorageMode::Sqlite {
+        paths.push(std::path::absolute(crate::sqlite::db_path()).unwrap());
+    }
+
+    paths
+}
+
+/// Starts watching the timesheet files, invalidating the cache and notifying the frontend on change,
+/// and of the budgets the change pushed past their warning threshold.
+///
+/// Parent directories are watched rather than the files themselves,
+/// since editors and sync clients usually replace the file instead of writing to it.
+pub fn watch_timesheets(app: AppHandle) -> notify::Result<TimesheetWatcher> {
+    let paths = watched_paths();
+
+    let watched = paths.clone();
+    let handle = app.clone();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(500),
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                if events
+                    .iter()
+                    .any(|e| watched.iter().any(|w| e.path.starts_with(w)))
+                {
+                    let cache = handle.state::<EntryCache>();
+                    cache.invalidate();
+                    if let Err(e) = handle.emit(ENTRIES_CHANGED_EVENT, ()) {
+                        eprintln!("[ERROR] Failed to emit {ENTRIES_CHANGED_EVENT}: {e}");
+                    }
+                    for status in budgets::check_thresholds(&cache) {
+                        println!("[WARN] Budget {:.0}% used: {:?}", status.percent_used, status.budget);
+                        if let Err(e) = handle.emit(budgets::BUDGET_WARNING_EVENT, &status) {
+                            eprintln!("[ERROR] Failed to emit {}: {e}", budgets::BUDGET_WARNING_EVENT);
+                        }
+                    }
+                }
+            }
+            Err(e) => eprintln!("[ERROR] Timesheet watcher: {e}"),
+        },
+    )?;
+
+    for path in paths.iter() {
+        if path.is_dir() {
+            debouncer.watcher().watch(path, RecursiveMode::NonRecursive)?;
+        } else {
+            let dir = path.parent().expect("Timesheet path should have a parent directory");
+            debouncer.watcher().watch(dir, RecursiveMode::NonRecursive)?;
+        }
+    }
+    let cache = app.state::<EntryCache>();
+    cache.set_watching(true);
+    // Only budgets crossing their threshold from now on get a warning
+    budgets::check_thresholds(&cache);
+
+    Ok(TimesheetWatcher(Mutex::new(debouncer)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+
+    fn load_counting(loads: &Cell<u32>) -> Vec<TimeSheetEntry> {
+        loads.set(loads.get() + 1);
+        vec![TimeSheetEntry {
+            description: "Cached".to_string(),
+            start_time: Local::now(),
+            end_time: None,
+            tags: vec![],
+            properties: HashMap::new(),
+        }]
+    }
+
+    #[test]
+    fn test_cache_only_while_watching() {
+        let cache = EntryCache::new();
+        let loads = Cell::new(0);
+
+        cache.entries_with(|| load_counting(&loads));
+        cache.entries_with(|| load_counting(&loads));
+        assert_eq!(loads.get(), 2, "Should read from disk every time without a watcher");
+
+        cache.set_watching(true);
+        cache.entries_with(|| load_counting(&loads));
+        let entries = cache.entries_with(|| load_counting(&loads));
+        assert_eq!(loads.get(), 3);
+        assert_eq!(entries[0].description, "Cached");
+
+        cache.invalidate();
+        cache.entries_with(|| load_counting(&loads));
+        assert_eq!(loads.get(), 4);
+    }
+}
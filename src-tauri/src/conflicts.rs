@@ -0,0 +1,485 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::backup::{backup_dir, backups, timesheet_stem};
+use crate::cache::EntryCache;
+use crate::{format, read_timesheet, write_entries, TimeSheetEntry};
+
+/// Copy of the timesheet left next to it by a sync client that couldn't merge two versions.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictCopy {
+    pub name: String,
+    /// Epoch milliseconds
+    pub modified: i64,
+}
+
+/// Entry changed differently in the timesheet and in a conflict copy since their common version.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub copy: String,
+    pub start_time: DateTime<Local>,
+    pub base: Option<TimeSheetEntry>,
+    pub ours: Option<TimeSheetEntry>,
+    pub theirs: Option<TimeSheetEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Choice {
+    /// Keep the timesheet's version
+    Ours,
+    /// Keep the conflict copy's version
+    Theirs,
+    /// Keep both versions as separate entries
+    Both,
+}
+
+impl std::str::FromStr for Choice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ours" => Ok(Choice::Ours),
+            "theirs" => Ok(Choice::Theirs),
+            "both" => Ok(Choice::Both),
+            _ => Err(format!("Unknown choice {s}, expected ours, theirs or both")),
+        }
+    }
+}
+
+impl Conflict {
+    /// Resolves this conflict with `choice`.
+    pub fn resolve(&self, choice: Choice) -> Resolution {
+        Resolution {
+            copy: self.copy.clone(),
+            start_time: self.start_time,
+            choice,
+        }
+    }
+}
+
+/// Manual choice for one reported [`Conflict`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resolution {
+    pub copy: String,
+    pub start_time: DateTime<Local>,
+    pub choice: Choice,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MergeReport {
+    /// Copies merged into the timesheet and archived
+    pub merged: Vec<String>,
+    /// Conflicts left without a resolution, their copies were left untouched
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Sync client naming schemes: `timesheet (1).csv`, `timesheet (Bob's conflicted copy).csv`,
+/// `timesheet-DESKTOP-1234.csv`, `timesheet-conflict-2025-06-10.csv`...
+fn is_conflict_copy(path: &Path, timesheet_path: &Path, stem: &str) -> bool {
+    if path == timesheet_path || path.extension() != timesheet_path.extension() {
+        return false;
+    }
+    let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+        return false;
+    };
+    let Some(rest) = name.strip_prefix(stem) else {
+        return false;
+    };
+
+    if let Some(inner) = rest.strip_prefix(" (").and_then(|r| r.strip_suffix(')')) {
+        return inner.parse::<u32>().is_ok() || inner.contains("conflicted copy");
+    }
+    let Some(suffix) = rest.strip_prefix('-') else {
+        return false;
+    };
+    // Host names are upper case, unlike the names given to exports like `timesheet-june.csv`
+    suffix.starts_with("conflict")
+        || (suffix.chars().any(|c| c.is_ascii_uppercase())
+            && suffix
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-'))
+}
+
+fn conflict_copy_paths() -> Vec<PathBuf> {
+    let timesheet_path = std::path::absolute(std::env::var("TIMESHEET_PATH").unwrap()).unwrap();
+    let stem = timesheet_stem();
+    let dir = timesheet_path.parent().unwrap();
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.is_file() && is_conflict_copy(p, &timesheet_path, &stem))
+        .filter(|p| format::is_timesheet(p))
+        .collect();
+    paths.sort();
+
+    paths
+}
+
+#[tauri::command]
+pub fn find_conflict_copies() -> Vec<ConflictCopy> {
+    conflict_copy_paths()
+        .into_iter()
+        .map(|path| ConflictCopy {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            modified: std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .map(|m| chrono::DateTime::<Local>::from(m).timestamp_millis())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Identifies entries by start time, with an occurrence counter for the rare shared ones.
+///
+/// Unlike the description, the start time is rarely what gets edited,
+/// so a renamed entry shows up as changed rather than as removed and added.
+fn keyed(entries: Vec<TimeSheetEntry>) -> Vec<((DateTime<Local>, usize), TimeSheetEntry)> {
+    let mut occurrences = HashMap::<DateTime<Local>, usize>::new();
+    entries
+        .into_iter()
+        .map(|entry| {
+            let start_time = entry.start_time;
+            let occurrence = occurrences.entry(start_time).or_default();
+            *occurrence += 1;
+            ((start_time, *occurrence), entry)
+        })
+        .collect()
+}
+
+/// Number of entries only one of the two has, told apart by start time and description.
+fn symmetric_difference(a: &[TimeSheetEntry], b: &[TimeSheetEntry]) -> usize {
+    let mut counts = HashMap::<(DateTime<Local>, &str), isize>::new();
+    for entry in a {
+        *counts
+            .entry((entry.start_time, &entry.description))
+            .or_default() += 1;
+    }
+    for entry in b {
+        *counts
+            .entry((entry.start_time, &entry.description))
+            .or_default() -= 1;
+    }
+
+    counts.values().map(|count| count.unsigned_abs()).sum()
+}
+
+/// Version both sides last had in common, approximated by the backup closest to the copy.
+///
+/// Backups are taken before each of our writes, so the ones after the split also contain
+/// our own changes, while the copy only differs from the common version by theirs.
+fn find_base(theirs: &[TimeSheetEntry]) -> Vec<TimeSheetEntry> {
+    backups()
+        .into_iter()
+        .map(|(path, _)| read_timesheet(path))
+        .min_by_key(|backup| symmetric_difference(backup, theirs))
+        .unwrap_or_default()
+}
+
+/// Three-way merge of `theirs` into `ours`, returning the merged entries and what conflicts.
+///
+/// Entries changed on only one side since `base` take that side's version,
+/// the ones changed on both sides differently need a [`Choice`] or are reported.
+fn merge(
+    base: Vec<TimeSheetEntry>,
+    ours: Vec<TimeSheetEntry>,
+    theirs: Vec<TimeSheetEntry>,
+    copy: &str,
+    resolutions: &[Resolution],
+) -> (Vec<TimeSheetEntry>, Vec<Conflict>) {
+    let base: HashMap<_, _> = keyed(base).into_iter().collect();
+    let ours = keyed(ours);
+    let theirs = keyed(theirs);
+    let theirs_map: HashMap<_, _> = theirs.iter().cloned().collect();
+    let ours_keys: HashSet<_> = ours.iter().map(|(k, _)| *k).collect();
+
+    // Ours first in their order, then what only theirs has
+    let keys: Vec<_> = ours
+        .iter()
+        .map(|(k, _)| *k)
+        .chain(
+            theirs
+                .iter()
+                .map(|(k, _)| *k)
+                .filter(|k| !ours_keys.contains(k)),
+        )
+        .collect();
+    let ours: HashMap<_, _> = ours.into_iter().collect();
+
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let (b, o, t) = (base.get(&key), ours.get(&key), theirs_map.get(&key));
+        if o == t || b == t {
+            merged.extend(o.cloned());
+        } else if b == o {
+            merged.extend(t.cloned());
+        } else {
+            let choice = resolutions
+                .iter()
+                .find(|r| r.copy == copy && r.start_time == key.0)
+                .map(|r| r.choice);
+            match choice {
+                Some(Choice::Ours) => merged.extend(o.cloned()),
+                Some(Choice::Theirs) => merged.extend(t.cloned()),
+                Some(Choice::Both) => merged.extend(o.into_iter().chain(t).cloned()),
+                None => conflicts.push(Conflict {
+                    copy: copy.to_string(),
+                    start_time: key.0,
+                    base: b.cloned(),
+                    ours: o.cloned(),
+                    theirs: t.cloned(),
+                }),
+            }
+        }
+    }
+
+    (merged, conflicts)
+}
+
+fn archive(path: &Path) -> std::io::Result<()> {
+    let dir = backup_dir().join("conflicts");
+    std::fs::create_dir_all(&dir)?;
+    let name = format!(
+        "{}-{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        path.file_name().unwrap().to_string_lossy()
+    );
+    let target = dir.join(name);
+    // Renaming fails when the backup directory is on another filesystem
+    if std::fs::rename(path, &target).is_err() {
+        std::fs::copy(path, &target)?;
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Merges every conflict copy into the timesheet and archives it in the backup directory.
+///
+/// A copy with conflicts not covered by `resolutions` is left alone and its conflicts reported,
+/// so they can be resolved and the merge run again.
+#[tauri::command]
+pub fn merge_conflict_copies(
+    cache: &EntryCache,
+    resolutions: Vec<Resolution>,
+) -> Result<MergeReport, String> {
+    // The current entries, the csv being stale or missing with the other storage modes
+    let mut ours = cache.entries().to_vec();
+
+    let mut report = MergeReport::default();
+    let mut merged_paths = Vec::new();
+    for path in conflict_copy_paths() {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let theirs = read_timesheet(&path);
+        let base = find_base(&theirs);
+
+        let (merged, conflicts) = merge(base, ours.clone(), theirs, &name, &resolutions);
+        if conflicts.is_empty() {
+            ours = merged;
+            report.merged.push(name);
+            merged_paths.push(path);
+        } else {
+            report.conflicts.extend(conflicts);
+        }
+    }
+
+    if !merged_paths.is_empty() {
+        write_entries(cache, &ours);
+        println!("[INFO] Merged conflict copies {:?}", report.merged);
+        for path in merged_paths.iter() {
+            archive(path).map_err(|e| format!("Failed to archive {}: {e}", path.display()))?;
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_entries, lock_test_env};
+    use chrono::{DateTime, Duration};
+
+    fn entry(description: &str, minutes: i64) -> TimeSheetEntry {
+        let start_time = DateTime::from_timestamp_millis(1_750_000_000_000)
+            .unwrap()
+            .with_timezone(&Local)
+            + Duration::minutes(minutes);
+        TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(1)),
+            tags: vec!["test".to_string()],
+            properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_is_conflict_copy() {
+        let timesheet = Path::new("/sync/timesheet.csv");
+        let check = |path: &str| is_conflict_copy(Path::new(path), timesheet, "timesheet");
+
+        assert!(check("/sync/timesheet (1).csv"));
+        assert!(check(
+            "/sync/timesheet (Bob's conflicted copy 2025-06-10).csv"
+        ));
+        assert!(check("/sync/timesheet-DESKTOP-4KQ1.csv"));
+        assert!(check("/sync/timesheet-conflict-2025-06-10.csv"));
+        assert!(!check("/sync/timesheet-export.csv"));
+        assert!(!check("/sync/timesheet-june.csv"));
+        assert!(!check("/sync/timesheet (June export).csv"));
+        assert!(!check("/sync/timesheet.csv"));
+        assert!(!check("/sync/timesheet.trash.json"));
+        assert!(!check("/sync/timesheet (1).xlsx"));
+        assert!(!check("/sync/timesheets.csv"));
+    }
+
+    #[test]
+    fn test_three_way_merge() {
+        let base = vec![
+            entry("Same", 0),
+            entry("Renamed by us", 1),
+            entry("Deleted by them", 2),
+            entry("Both edited", 3),
+        ];
+        let ours = vec![
+            entry("Same", 0),
+            entry("Our name", 1),
+            entry("Deleted by them", 2),
+            entry("Our edit", 3),
+            entry("Added by us", 4),
+        ];
+        let theirs = vec![
+            entry("Same", 0),
+            entry("Renamed by us", 1),
+            entry("Their edit", 3),
+            entry("Added by them", 5),
+        ];
+
+        let (merged, conflicts) = merge(base.clone(), ours.clone(), theirs.clone(), "copy", &[]);
+        let descriptions: Vec<&str> = merged.iter().map(|e| e.description.as_str()).collect();
+        assert_eq!(
+            descriptions,
+            vec!["Same", "Our name", "Added by us", "Added by them"]
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].ours.as_ref().unwrap().description, "Our edit");
+        assert_eq!(
+            conflicts[0].theirs.as_ref().unwrap().description,
+            "Their edit"
+        );
+
+        let resolution = Resolution {
+            copy: "copy".to_string(),
+            start_time: conflicts[0].start_time,
+            choice: Choice::Theirs,
+        };
+        let (merged, conflicts) = merge(base, ours, theirs, "copy", &[resolution]);
+        assert!(conflicts.is_empty());
+        assert!(merged.iter().any(|e| e.description == "Their edit"));
+    }
+
+    fn write_copy(path: &Path, entries: &[TimeSheetEntry]) {
+        let mut writer = csv::Writer::from_path(path).unwrap();
+        for e in entries {
+            writer.serialize(crate::TimeSheetEntryRaw::from(e)).unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    /// Merges a copy adding an entry into the timesheet stored per `STORAGE_MODE`.
+    fn merge_in_storage_mode(mode: &str, dir: &Path) {
+        let cache = EntryCache::new();
+        std::env::set_var("TIMESHEET_PATH", dir.join("timesheet.csv"));
+        std::env::set_var("STORAGE_MODE", mode);
+        // The csv left behind when switching away from it
+        write_copy(&dir.join("timesheet.csv"), &[entry("Old", 0)]);
+
+        write_entries(&cache, &[entry("First", 1), entry("Second", 2)]);
+        write_copy(
+            &dir.join("timesheet-LAPTOP.csv"),
+            &[entry("First", 1), entry("Theirs", 3)],
+        );
+        let report = merge_conflict_copies(&cache, vec![]).unwrap();
+        let descriptions: Vec<String> = get_entries().into_iter().map(|e| e.description).collect();
+        std::env::remove_var("STORAGE_MODE");
+
+        assert_eq!(report.merged, vec!["timesheet-LAPTOP.csv"]);
+        assert_eq!(descriptions, vec!["First", "Second", "Theirs"]);
+    }
+
+    #[test]
+    fn test_merge_into_event_log() {
+        let _env = lock_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("EVENT_LOG_DIR", dir.path().join("events"));
+        std::env::set_var("MACHINE_NAME", "desktop");
+        std::env::remove_var("BACKUP_DIR");
+        merge_in_storage_mode("eventlog", dir.path());
+        std::env::remove_var("EVENT_LOG_DIR");
+        std::env::remove_var("MACHINE_NAME");
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_merge_into_sqlite() {
+        let _env = lock_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_DB_PATH", dir.path().join("timesheet.db"));
+        std::env::remove_var("BACKUP_DIR");
+        merge_in_storage_mode("sqlite", dir.path());
+        std::env::remove_var("TIMESHEET_DB_PATH");
+    }
+
+    #[test]
+    fn test_merge_conflict_copies() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        let timesheet_path = dir.path().join("timesheet.csv");
+        std::env::set_var("TIMESHEET_PATH", &timesheet_path);
+        std::env::remove_var("BACKUP_DIR");
+
+        let write_copy =
+            |name: &str, entries: &[TimeSheetEntry]| write_copy(&dir.path().join(name), entries);
+
+        // Common version, then both sides edit it or add their own entry
+        write_entries(&cache, &[entry("Common", 0)]);
+        write_entries(&cache, &[entry("Edited by us", 0), entry("Ours", 1)]);
+        write_copy("timesheet (1).csv", &[entry("Edited by them", 0)]);
+        write_copy(
+            "timesheet-LAPTOP.csv",
+            &[entry("Common", 0), entry("Theirs", 2)],
+        );
+
+        // Some other csv named like a copy
+        std::fs::write(
+            dir.path().join("timesheet-REPORT.csv"),
+            "\u{feff}Date;Description;Hours\n",
+        )
+        .unwrap();
+
+        assert_eq!(find_conflict_copies().len(), 2);
+        let report = merge_conflict_copies(&cache, vec![]).unwrap();
+        assert_eq!(report.merged, vec!["timesheet-LAPTOP.csv"]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].copy, "timesheet (1).csv");
+
+        let descriptions: Vec<String> = get_entries().into_iter().map(|e| e.description).collect();
+        assert_eq!(descriptions, vec!["Edited by us", "Ours", "Theirs"]);
+        assert_eq!(find_conflict_copies().len(), 1);
+        let archived = dir.path().join("timesheet.backups/conflicts");
+        assert_eq!(archived.read_dir().unwrap().count(), 1);
+
+        let resolution = report.conflicts[0].resolve("both".parse().unwrap());
+        assert_eq!(resolution.choice, Choice::Both);
+        let report = merge_conflict_copies(&cache, vec![resolution]).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(get_entries().len(), 4);
+        assert!(find_conflict_copies().is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::ics::{self, CalendarEvent, SkippedEvent};
+use crate::{timesheet_sidecar_path, write_entries, TimeSheetEntry};
+
+/// Tags and Jira key given to calendar events matching every condition set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventRule {
+    /// Case-insensitive part of the event title
+    pub title_contains: Option<String>,
+    /// Case-insensitive part of the organizer's name or address
+    pub organizer_contains: Option<String>,
+    pub tags: Vec<String>,
+    pub jira: Option<String>,
+}
+
+impl EventRule {
+    fn matches(&self, event: &CalendarEvent) -> bool {
+        let contains = |text: &str, part: &str| text.to_lowercase().contains(&part.to_lowercase());
+        if self.title_contains.is_none() && self.organizer_contains.is_none() {
+            return false;
+        }
+        self.title_contains
+            .as_ref()
+            .is_none_or(|part| contains(&event.summary, part))
+            && self.organizer_contains.as_ref().is_none_or(|part| {
+                event
+                    .organizer
+                    .as_ref()
+                    .is_some_and(|organizer| contains(organizer, part))
+            })
+    }
+}
+
+/// Calendar event waiting to be reviewed before it becomes an entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    /// Event UID and start, so importing the same calendar again replaces the drafts
+    pub id: String,
+    pub entry: TimeSheetEntry,
+    pub organizer: Option<String>,
+    /// Whether it overlaps an entry of the timesheet, filled in when listed
+    #[serde(default, skip_deserializing)]
+    pub overlaps: bool,
+}
+
+/// Drafts accepted into the timesheet, and the ones left out because they overlap an entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AcceptReport {
+    pub accepted: usize,
+    pub overlapping: Vec<String>,
+}
+
+/// Drafts after an import, and the calendar's events that couldn't be imported.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub drafts: Vec<Draft>,
+    pub skipped: Vec<SkippedEvent>,
+}
+
+/// `EVENT_RULES_PATH`, or `<timesheet>.event_rules.json` next to `TIMESHEET_PATH`.
+fn rules_path() -> PathBuf {
+    match std::env::var("EVENT_RULES_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("event_rules.json"),
+    }
+}
+
+/// `DRAFTS_PATH`, or `<timesheet>.drafts.json` next to `TIMESHEET_PATH`.
+fn drafts_path() -> PathBuf {
+    match std::env::var("DRAFTS_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("drafts.json"),
+    }
+}
+
+pub fn load_rules() -> Vec<EventRule> {
+    let path = rules_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Vec::new();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Event rules file should be valid json")
+}
+
+fn load_drafts() -> Vec<Draft> {
+    let path = drafts_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Vec::new();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Drafts file should be valid json")
+}
+
+fn write_drafts(drafts: &[Draft]) {
+    let file = std::fs::File::create(drafts_path()).unwrap();
+    serde_json::to_writer_pretty(file, drafts).unwrap();
+}
+
+/// Running entries count as going on until now.
+fn overlaps(a: &TimeSheetEntry, b: &TimeSheetEntry, now: DateTime<Local>) -> bool {
+    a.start_time < b.end_time.unwrap_or(now) && b.start_time < a.end_time.unwrap_or(now)
+}
+
+fn overlaps_any(entry: &TimeSheetEntry, entries: &[TimeSheetEntry]) -> bool {
+    let now = Local::now();
+    entries.iter().any(|e| overlaps(entry, e, now))
+}
+
+/// Maps an event to a draft, applying every matching rule.
+///
+/// Categories and `X-TIMESHEET-` fields are kept, so exported entries come back as they were.
+pub fn to_draft(event: CalendarEvent, rules: &[EventRule]) -> Draft {
+    let matching: Vec<&EventRule> = rules.iter().filter(|r| r.matches(&event)).collect();
+    let mut tags = event.categories;
+    let mut properties = event.properties;
+    for rule in matching {
+        for tag in &rule.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if let Some(jira) = &rule.jira {
+            properties.entry("jira".to_string()).or_insert(jira.clone());
+        }
+    }
+
+    Draft {
+        id: format!("{}/{}", event.uid, event.start.timestamp_millis()),
+        entry: TimeSheetEntry {
+            description: event.summary,
+            start_time: event.start,
+            end_time: Some(event.end),
+            tags,
+            properties,
+        },
+        organizer: event.organizer,
+        overlaps: false,
+    }
+}
+
+#[tauri::command]
+pub fn get_event_rules() -> Vec<EventRule> {
+    load_rules()
+}
+
+/// Replaces the rules, applied in order when importing.
+#[tauri::command]
+pub fn save_event_rules(rules: Vec<EventRule>) -> bool {
+    let file = std::fs::File::create(rules_path()).unwrap();
+    serde_json::to_writer_pretty(file, &rules).unwrap();
+
+    true
+}
+
+#[tauri::command]
+pub fn get_drafts(cache: &EntryCache) -> Vec<Draft> {
+    let entries = cache.entries().to_vec();
+    let mut drafts = load_drafts();
+    for draft in drafts.iter_mut() {
+        draft.overlaps = overlaps_any(&draft.entry, &entries);
+    }
+
+    drafts
+}
+
+/// Reads the calendar's events as drafts, replacing the ones imported from it before.
+#[tauri::command]
+pub fn import_ics(cache: &EntryCache, path: String) -> Result<ImportReport, String> {
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let rules = load_rules();
+    let mut drafts = load_drafts();
+    let mut count = 0;
+    let (events, skipped) = ics::parse_ics(&content);
+    for draft in events.into_iter().map(|e| to_draft(e, &rules)) {
+        match drafts.iter_mut().find(|d| d.id == draft.id) {
+            Some(existing) => *existing = draft,
+            None => drafts.push(draft),
+        }
+        count += 1;
+    }
+    drafts.sort_by_key(|d| d.entry.start_time);
+    write_drafts(&drafts);
+    println!("[INFO] Imported {count} calendar events from {path} as drafts");
+    for event in skipped.iter() {
+        println!("[INFO] Skipped \"{}\": {}", event.summary, event.reason);
+    }
+
+    Ok(ImportReport {
+        drafts: get_drafts(cache),
+        skipped,
+    })
+}
+
+/// Edits a draft before accepting it.
+#[tauri::command]
+pub fn update_draft(id: String, entry: TimeSheetEntry) -> bool {
+    let mut drafts = load_drafts();
+    let Some(draft) = drafts.iter_mut().find(|d| d.id == id) else {
+        return false;
+    };
+    draft.entry = entry;
+    write_drafts(&drafts);
+
+    true
+}
+
+#[tauri::command]
+pub fn discard_drafts(ids: Vec<String>) -> usize {
+    let mut drafts = load_drafts();
+    let len = drafts.len();
+    drafts.retain(|d| !ids.contains(&d.id));
+    write_drafts(&drafts);
+
+    len - drafts.len()
+}
+
+/// Adds the drafts to the timesheet, leaving the ones overlapping an entry in the drafts.
+#[tauri::command]
+pub fn accept_drafts(cache: &EntryCache, ids: Vec<String>) -> AcceptReport {
+    let mut entries = cache.entries().to_vec();
+    let mut drafts = load_drafts();
+    let mut report = AcceptReport {
+        accepted: 0,
+        overlapping: Vec::new(),
+    };
+    drafts.retain(|draft| {
+        if !ids.contains(&draft.id) {
+            return true;
+        }
+        // Checked against the drafts accepted just before as well
+        if overlaps_any(&draft.entry, &entries) {
+            report.overlapping.push(draft.id.clone());
+            return true;
+        }
+        entries.push(draft.entry.clone());
+        report.accepted += 1;
+        false
+    });
+
+    if report.accepted > 0 {
+        write_entries(cache, &entries);
+        write_drafts(&drafts);
+    }
+    println!(
+        "[INFO] Accepted {} drafts, skipped {} overlapping entries",
+        report.accepted,
+        report.overlapping.len()
+    );
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock_test_env;
+    use chrono::{Duration, TimeZone};
+
+    #[test]
+    fn test_import_and_accept() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TOGGL_SHEET_PATH");
+        std::env::remove_var("DRAFTS_PATH");
+        std::env::remove_var("EVENT_RULES_PATH");
+
+        save_event_rules(vec![
+            EventRule {
+                title_contains: Some("standup".to_string()),
+                tags: vec!["meeting".to_string()],
+                jira: Some("PROJ-1".to_string()),
+                ..Default::default()
+            },
+            EventRule {
+                organizer_contains: Some("@client.com".to_string()),
+                tags: vec!["client".to_string()],
+                ..Default::default()
+            },
+        ]);
+
+        let start = Local.with_ymd_and_hms(2025, 6, 10, 9, 0, 0).unwrap();
+        write_entries(
+            &cache,
+            &[TimeSheetEntry {
+                description: "Deep work".to_string(),
+                start_time: start + Duration::hours(2),
+                end_time: Some(start + Duration::hours(3)),
+                tags: vec![],
+                properties: Default::default(),
+            }],
+        );
+
+        let path = dir.path().join("calendar.ics");
+        std::fs::write(
+            &path,
+            "BEGIN:VCALENDAR\n\
+             BEGIN:VEVENT\nUID:1\nSUMMARY:Daily Standup\nORGANIZER:mailto:lead@client.com\n\
+             DTSTART:20250610T090000\nDTEND:20250610T091500\nEND:VEVENT\n\
+             BEGIN:VEVENT\nUID:2\nSUMMARY:Review\nDTSTART:20250610T103000\nDTEND:20250610T113000\nEND:VEVENT\n\
+             BEGIN:VEVENT\nUID:3\nSUMMARY:Same slot\nDTSTART:20250610T091000\nDTEND:20250610T093000\nEND:VEVENT\n\
+             BEGIN:VEVENT\nUID:4\nSUMMARY:Weekly sync\nDTSTART:20230102T140000\nDTEND:20230102T150000\n\
+             RRULE:FREQ=WEEKLY\nEND:VEVENT\n\
+             END:VCALENDAR\n",
+        )
+        .unwrap();
+
+        let ImportReport { drafts, skipped } =
+            import_ics(&cache, path.to_string_lossy().to_string()).unwrap();
+        assert_eq!(drafts.len(), 3);
+        assert_eq!(
+            skipped,
+            vec![SkippedEvent {
+                summary: "Weekly sync".to_string(),
+                reason: "recurring".to_string()
+            }]
+        );
+        assert_eq!(drafts[0].entry.tags, vec!["meeting", "client"]);
+        assert_eq!(drafts[0].entry.properties["jira"], "PROJ-1");
+        assert!(drafts[1].entry.tags.is_empty());
+        assert_eq!(
+            drafts.iter().map(|d| d.overlaps).collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+        assert_eq!(
+            import_ics(&cache, path.to_string_lossy().to_string())
+                .unwrap()
+                .drafts
+                .len(),
+            3,
+            "Importing again replaces the drafts"
+        );
+
+        let ids: Vec<String> = drafts.iter().map(|d| d.id.clone()).collect();
+        let report = accept_drafts(&cache, ids.clone());
+        assert_eq!(report.accepted, 1);
+        assert_eq!(report.overlapping, vec![ids[1].clone(), ids[2].clone()]);
+        assert_eq!(crate::get_entries().len(), 2);
+        assert_eq!(get_drafts(&cache).len(), 2, "Overlapping drafts are kept");
+
+        assert_eq!(discard_drafts(ids), 2);
+        assert!(get_drafts(&cache).is_empty());
+    }
+}
@@ -0,0 +1,500 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{read_timesheet, timesheet_sidecar_path, TimeSheetEntry};
+
+/// Own log size past which it gets compacted on startup.
+const DEFAULT_COMPACT_THRESHOLD: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// One change to one entry, as appended to a machine's log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub entry_id: String,
+    pub kind: EventKind,
+    /// Epoch milliseconds
+    pub timestamp: i64,
+    pub machine: String,
+    /// Full entry after the change, absent for deletions
+    pub entry: Option<TimeSheetEntry>,
+}
+
+/// `EVENT_LOG_DIR`, or `<timesheet>.events` next to `TIMESHEET_PATH`.
+///
+/// Every machine appends to its own `<machine>.jsonl` in there, so sync clients never see
+/// two machines writing the same file, and the state is rebuilt by replaying all of them.
+pub fn event_log_dir() -> PathBuf {
+    match std::env::var("EVENT_LOG_DIR") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("events"),
+    }
+}
+
+/// `MACHINE_NAME`, falling back on the host name.
+fn machine_name() -> String {
+    ["MACHINE_NAME", "COMPUTERNAME", "HOSTNAME"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|h| h.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+fn own_log_path() -> PathBuf {
+    event_log_dir().join(format!("{}.jsonl", machine_name()))
+}
+
+/// Ids sort by creation time, which gives the materialized entries a stable order.
+///
+/// The padded sequence keeps entries created within the same millisecond in order,
+/// and the process id keeps two instances on the same machine from reusing an id.
+fn new_entry_id(now: DateTime<Local>, machine: &str) -> String {
+    static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+    format!(
+        "{:013}-{machine}-{}-{:010}",
+        now.timestamp_millis(),
+        std::process::id(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn read_log(path: &Path) -> Vec<Event> {
+    let file = std::fs::File::open(path).unwrap();
+    BufReader::new(file)
+        .lines()
+        .map(|line| line.unwrap())
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<Event>(&line) {
+            Ok(event) => Some(event),
+            // Likely a line still being synced, it will be complete next time
+            Err(e) => {
+                eprintln!(
+                    "[WARN] Skipping unreadable event in {}: {e}",
+                    path.display()
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Events from every machine's log, ordered by timestamp.
+fn read_all_events() -> Vec<Event> {
+    let dir = event_log_dir();
+    if !std::fs::exists(&dir).unwrap() {
+        return Vec::new();
+    }
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    paths.sort();
+
+    let mut events: Vec<Event> = paths.iter().flat_map(|p| read_log(p)).collect();
+    // Within a millisecond, creations go before the edits that can only follow them,
+    // and the sort is stable so a machine's events keep their order
+    events.sort_by(|a, b| {
+        a.timestamp
+            .cmp(&b.timestamp)
+            .then_with(|| a.kind.cmp(&b.kind))
+            .then_with(|| a.machine.cmp(&b.machine))
+    });
+
+    events
+}
+
+fn append_events(path: &Path, events: &[Event]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap();
+    let mut buffer = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut buffer, event).unwrap();
+        buffer.push(b'\n');
+    }
+    file.write_all(&buffer).unwrap();
+    file.sync_all().unwrap();
+}
+
+/// Replays events into the current entries, each keyed by its id.
+///
+/// The latest event for an entry wins, whichever machine it came from,
+/// and an update for an unknown entry recreates it.
+pub fn materialize(events: &[Event]) -> Vec<(String, TimeSheetEntry)> {
+    let mut state = BTreeMap::<String, Option<TimeSheetEntry>>::new();
+    for event in events {
+        let entry = match event.kind {
+            EventKind::Deleted => None,
+            EventKind::Created | EventKind::Updated => event.entry.clone(),
+        };
+        state.insert(event.entry_id.clone(), entry);
+    }
+
+    state
+        .into_iter()
+        .filter_map(|(id, entry)| Some((id, entry?)))
+        .collect()
+}
+
+/// Seeds the log from the csv timesheet when switching to this storage mode.
+fn bootstrap_from_csv() {
+    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
+    if std::fs::exists(event_log_dir()).unwrap() || !std::fs::exists(&timesheet_path).unwrap() {
+        return;
+    }
+
+    let now = Local::now();
+    let machine = machine_name();
+    let events: Vec<Event> = read_timesheet(&timesheet_path)
+        .into_iter()
+        .map(|entry| Event {
+            entry_id: new_entry_id(now, &machine),
+            kind: EventKind::Created,
+            timestamp: now.timestamp_millis(),
+            machine: machine.clone(),
+            entry: Some(entry),
+        })
+        .collect();
+    append_events(&own_log_path(), &events);
+
+    println!(
+        "[INFO] Event log seeded with {} entries from {timesheet_path}",
+        events.len()
+    );
+}
+
+fn load_with_ids() -> Vec<(String, TimeSheetEntry)> {
+    bootstrap_from_csv();
+    materialize(&read_all_events())
+}
+
+pub fn load() -> Vec<TimeSheetEntry> {
+    load_with_ids()
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Appends the events turning the current state into `entries`.
+pub fn save(entries: &[TimeSheetEntry]) {
+    let current = load_with_ids();
+    let events = diff(&current, entries, Local::now(), &machine_name());
+    if !events.is_empty() {
+        append_events(&own_log_path(), &events);
+    }
+}
+
+/// For each of `entries`, the position of the entry of `current` it is, if any.
+///
+/// Entries are matched by description and start time first. Edits replace entries in place,
+/// so the ones left are then matched by position, as long as either the description or the
+/// start time didn't change. Whatever is still unmatched was created or deleted.
+pub(crate) fn match_entries(
+    current: &[&TimeSheetEntry],
+    entries: &[TimeSheetEntry],
+) -> Vec<Option<usize>> {
+    let mut by_key = HashMap::<(&str, DateTime<Local>), VecDeque<usize>>::new();
+    for (i, entry) in current.iter().enumerate() {
+        by_key
+            .entry((entry.description.as_str(), entry.start_time))
+            .or_default()
+            .push_back(i);
+    }
+
+    let mut matched = vec![false; current.len()];
+    let mut matches: Vec<Option<usize>> = entries
+        .iter()
+        .map(|entry| {
+            let i = by_key
+                .get_mut(&(entry.description.as_str(), entry.start_time))?
+                .pop_front()?;
+            matched[i] = true;
+            Some(i)
+        })
+        .collect();
+    for (j, m) in matches.iter_mut().enumerate() {
+        let Some(old) = current.get(j) else {
+            break;
+        };
+        let edited =
+            old.description == entries[j].description || old.start_time == entries[j].start_time;
+        if m.is_none() && !matched[j] && edited {
+            matched[j] = true;
+            *m = Some(j);
+        }
+    }
+
+    matches
+}
+
+/// Events turning `current` into `entries`, matched per [`match_entries`].
+fn diff(
+    current: &[(String, TimeSheetEntry)],
+    entries: &[TimeSheetEntry],
+    now: DateTime<Local>,
+    machine: &str,
+) -> Vec<Event> {
+    let event = |entry_id: String, kind: EventKind, entry: Option<&TimeSheetEntry>| Event {
+        entry_id,
+        kind,
+        timestamp: now.timestamp_millis(),
+        machine: machine.to_string(),
+        entry: entry.cloned(),
+    };
+
+    let stored: Vec<&TimeSheetEntry> = current.iter().map(|(_, entry)| entry).collect();
+    let matches = match_entries(&stored, entries);
+    let mut matched = vec![false; current.len()];
+    for i in matches.iter().flatten() {
+        matched[*i] = true;
+    }
+
+    let mut events = Vec::new();
+    for (entry, m) in entries.iter().zip(matches) {
+        match m {
+            Some(i) if current[i].1 == *entry => {}
+            Some(i) => events.push(event(current[i].0.clone(), EventKind::Updated, Some(entry))),
+            None => events.push(event(
+                new_entry_id(now, machine),
+                EventKind::Created,
+                Some(entry),
+            )),
+        }
+    }
+    for (i, (id, _)) in current.iter().enumerate() {
+        if !matched[i] {
+            events.push(event(id.clone(), EventKind::Deleted, None));
+        }
+    }
+
+    events
+}
+
+/// Rewrites this machine's log keeping only its latest event per entry.
+///
+/// Since the latest event wins when replaying, dropping the superseded ones
+/// doesn't change the result. Other machines' logs are left for them to compact.
+#[tauri::command]
+pub fn compact_event_log() -> usize {
+    let path = own_log_path();
+    if !std::fs::exists(&path).unwrap() {
+        return 0;
+    }
+
+    let events = read_log(&path);
+    let mut latest = HashMap::<&str, usize>::new();
+    for (i, event) in events.iter().enumerate() {
+        latest.insert(event.entry_id.as_str(), i);
+    }
+    let compacted: Vec<Event> = events
+        .iter()
+        .enumerate()
+        .filter(|(i, e)| latest.get(e.entry_id.as_str()) == Some(i))
+        .map(|(_, e)| e.clone())
+        .collect();
+
+    let temp_path = path.with_extension("jsonl.tmp");
+    let _ = std::fs::remove_file(&temp_path);
+    append_events(&temp_path, &compacted);
+    std::fs::rename(&temp_path, &path).unwrap();
+
+    println!(
+        "[INFO] Compacted event log from {} to {} events",
+        events.len(),
+        compacted.len()
+    );
+
+    events.len() - compacted.len()
+}
+
+/// Compacts this machine's log if it grew past `EVENT_LOG_COMPACT_THRESHOLD` events.
+pub fn compact_if_needed() {
+    let threshold = std::env::var("EVENT_LOG_COMPACT_THRESHOLD")
+        .ok()
+        .map(|t| {
+            t.parse()
+                .expect("EVENT_LOG_COMPACT_THRESHOLD should be a number")
+        })
+        .unwrap_or(DEFAULT_COMPACT_THRESHOLD);
+
+    let path = own_log_path();
+    if std::fs::exists(&path).unwrap() && read_log(&path).len() > threshold {
+        compact_event_log();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::EntryCache;
+    use crate::{get_entries, lock_test_env, write_entries, StorageMode};
+    use chrono::Duration;
+
+    fn entry(description: &str, start_time: DateTime<Local>) -> TimeSheetEntry {
+        TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: None,
+            tags: vec!["test".to_string()],
+            properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let now = Local::now();
+        let current = vec![
+            ("a".to_string(), entry("Kept", now)),
+            (
+                "b".to_string(),
+                entry("Renamed", now + Duration::minutes(1)),
+            ),
+            (
+                "c".to_string(),
+                entry("Stopped", now + Duration::minutes(2)),
+            ),
+            (
+                "d".to_string(),
+                entry("Deleted", now + Duration::minutes(3)),
+            ),
+        ];
+        let mut stopped = entry("Stopped", now + Duration::minutes(2));
+        stopped.end_time = Some(now + Duration::minutes(3));
+        let entries = vec![
+            entry("Kept", now),
+            entry("New name", now + Duration::minutes(1)),
+            stopped,
+            entry("Created", now + Duration::minutes(4)),
+        ];
+
+        let events = diff(&current, &entries, now, "desktop");
+        let summary: Vec<(&str, EventKind)> = events
+            .iter()
+            .map(|e| (e.entry_id.as_str(), e.kind))
+            .collect();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[0], ("b", EventKind::Updated));
+        assert_eq!(summary[1], ("c", EventKind::Updated));
+        // Took the position of the deleted entry, but has nothing in common with it
+        assert_eq!(summary[2].1, EventKind::Created);
+        assert_eq!(summary[3], ("d", EventKind::Deleted));
+        assert_eq!(events[0].entry.as_ref().unwrap().description, "New name");
+    }
+
+    #[test]
+    fn test_bootstrap_keeps_order() {
+        let _env = lock_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::set_var("EVENT_LOG_DIR", dir.path().join("events"));
+        std::env::set_var("MACHINE_NAME", "laptop");
+
+        // Enough entries for the sequence to reach two digits, all seeded in the same millisecond
+        let now = Local::now();
+        let entries: Vec<TimeSheetEntry> = (0..12)
+            .map(|i| entry(&format!("Entry {i}"), now + Duration::minutes(i)))
+            .collect();
+        crate::write_timesheet(dir.path().join("timesheet.csv"), &entries);
+
+        let descriptions: Vec<String> = load().into_iter().map(|e| e.description).collect();
+        let expected: Vec<String> = entries.into_iter().map(|e| e.description).collect();
+        assert_eq!(descriptions, expected);
+
+        std::env::remove_var("MACHINE_NAME");
+        std::env::remove_var("EVENT_LOG_DIR");
+    }
+
+    #[test]
+    fn test_concurrent_machines_merge() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::set_var("STORAGE_MODE", "eventlog");
+        assert_eq!(StorageMode::from_env(), StorageMode::EventLog);
+
+        // Each machine has its own synced copy of the log directory
+        let laptop_dir = dir.path().join("laptop");
+        let desktop_dir = dir.path().join("desktop");
+        let switch_to = |machine: &str, log_dir: &Path| {
+            std::env::set_var("MACHINE_NAME", machine);
+            std::env::set_var("EVENT_LOG_DIR", log_dir);
+        };
+
+        // Stored with millisecond precision
+        let now = DateTime::from_timestamp_millis(Local::now().timestamp_millis())
+            .unwrap()
+            .with_timezone(&Local);
+        switch_to("laptop", &laptop_dir);
+        write_entries(
+            &cache,
+            &[
+                entry("First", now),
+                entry("Second", now + Duration::minutes(1)),
+            ],
+        );
+        std::fs::create_dir(&desktop_dir).unwrap();
+        std::fs::copy(
+            laptop_dir.join("laptop.jsonl"),
+            desktop_dir.join("laptop.jsonl"),
+        )
+        .unwrap();
+
+        // Both edit different entries before syncing again
+        let mut entries = get_entries();
+        entries.push(entry("Third", now + Duration::minutes(2)));
+        write_entries(&cache, &entries);
+
+        switch_to("desktop", &desktop_dir);
+        let mut entries = get_entries();
+        assert_eq!(entries.len(), 2);
+        entries[0].description = "First, from desktop".to_string();
+        write_entries(&cache, &entries);
+
+        std::fs::copy(
+            laptop_dir.join("laptop.jsonl"),
+            desktop_dir.join("laptop.jsonl"),
+        )
+        .unwrap();
+        let descriptions: Vec<String> = get_entries().into_iter().map(|e| e.description).collect();
+        assert_eq!(descriptions, vec!["First, from desktop", "Second", "Third"]);
+        assert!(!dir.path().join("timesheet.csv").exists());
+
+        // Compacting keeps the same state
+        let mut entries = get_entries();
+        for minutes in [5, 6] {
+            entries[1].end_time = Some(now + Duration::minutes(minutes));
+            write_entries(&cache, &entries);
+        }
+        assert_eq!(compact_event_log(), 1);
+        let entries = get_entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].description, "First, from desktop");
+        assert_eq!(entries[1].end_time, Some(now + Duration::minutes(6)));
+
+        std::env::remove_var("STORAGE_MODE");
+        std::env::remove_var("MACHINE_NAME");
+        std::env::remove_var("EVENT_LOG_DIR");
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::Write;
+use std::path::Path;
+
+use chrono::Duration;
+
+use crate::cache::EntryCache;
+use crate::rounding::rounded_durations;
+use crate::{parse_date, TimeSheetEntry};
+
+/// Separators and date order Excel expects when opening a csv, which follow the system's regional settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExcelLocale {
+    pub delimiter: u8,
+    pub decimal_separator: char,
+    pub date_format: &'static str,
+}
+
+/// Where the decimal separator is a comma, Excel splits csv columns on semicolons instead.
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "de", "fr", "es", "it", "nl", "pt", "ru", "pl", "cs", "da", "fi", "nb", "sv", "tr",
+];
+
+impl ExcelLocale {
+    /// From a locale like `de_DE.UTF-8` or `en-US`, falling back to ISO dates.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split('.').next().unwrap_or_default().replace('-', "_");
+        let language = tag.split('_').next().unwrap_or_default();
+        let decimal_comma = DECIMAL_COMMA_LANGUAGES.contains(&language);
+        let date_format = match language {
+            "en" if tag == "en_US" => "%m/%d/%Y",
+            "en" | "fr" | "es" | "it" | "pt" => "%d/%m/%Y",
+            "de" | "ru" | "pl" | "cs" | "da" | "fi" | "nb" | "tr" => "%d.%m.%Y",
+            "nl" => "%d-%m-%Y",
+            _ => "%Y-%m-%d",
+        };
+
+        ExcelLocale {
+            delimiter: if decimal_comma { b';' } else { b',' },
+            decimal_separator: if decimal_comma { ',' } else { '.' },
+            date_format,
+        }
+    }
+
+    /// `EXPORT_LOCALE`, or the system's locale, from the regional settings on Windows and macOS
+    /// and `LANG` and the like elsewhere.
+    pub fn from_env() -> Self {
+        let tag = std::env::var("EXPORT_LOCALE")
+            .ok()
+            .or_else(sys_locale::get_locale)
+            .unwrap_or_default();
+        Self::from_tag(&tag)
+    }
+
+    fn hours(&self, hours: f64) -> String {
+        format!("{hours:.2}").replace('.', &self.decimal_separator.to_string())
+    }
+}
+
+/// Excel runs cells starting like a formula, even from a csv.
+fn as_text(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_row(entry: &TimeSheetEntry, duration: Duration, locale: &ExcelLocale) -> [String; 8] {
+    let mut properties: Vec<String> = entry
+        .properties
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect();
+    properties.sort();
+
+    // Ends where the rounded duration does, so the columns add up
+    let end_time = entry.end_time.map(|_| entry.start_time + duration);
+    [
+        entry.start_time.format(locale.date_format).to_string(),
+        entry.start_time.format("%H:%M:%S").to_string(),
+        // Entries can go past midnight
+        end_time
+            .map(|end| end.format(locale.date_format).to_string())
+            .unwrap_or_default(),
+        end_time
+            .map(|end| end.format("%H:%M:%S").to_string())
+            .unwrap_or_default(),
+        // Running entries count until now, like in the totals
+        locale.hours(duration.num_milliseconds() as f64 / 3_600_000.0),
+        as_text(&entry.description),
+        as_text(&entry.tags.join(", ")),
+        as_text(&properties.join("; ")),
+    ]
+}
+
+/// Writes the entries as a csv Excel opens as is, returning how many were written.
+///
+/// The byte order mark makes Excel read it as UTF-8.
+pub fn write_excel_csv<'a>(
+    path: impl AsRef<Path>,
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+    locale: &ExcelLocale,
+) -> usize {
+    let mut file = std::fs::File::create(path).unwrap();
+    file.write_all("\u{feff}".as_bytes()).unwrap();
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(locale.delimiter)
+        .from_writer(file);
+    writer
+        .write_record([
+            "Date",
+            "Start",
+            "End date",
+            "End",
+            "Hours",
+            "Description",
+            "Tags",
+            "Properties",
+        ])
+        .unwrap();
+    let mut count = 0;
+    for (entry, duration) in rounded_durations(entries) {
+        writer
+            .write_record(to_row(entry, duration, locale))
+            .unwrap();
+        count += 1;
+    }
+    writer.flush().unwrap();
+
+    count
+}
+
+/// Exports a copy for Excel, leaving the timesheet itself untouched.
+#[tauri::command]
+pub fn export_excel(
+    cache: &EntryCache,
+    path: String,
+    from: &str,
+    to: &str,
+) -> Result<usize, String> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+
+    let entries = cache.in_range(from..=to);
+    let count = write_excel_csv(&path, &entries, &ExcelLocale::from_env());
+    println!("[INFO] Exported {count} entries to {path}");
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local, TimeZone};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_locales() {
+        let start = Local.with_ymd_and_hms(2025, 6, 10, 9, 0, 0).unwrap();
+        let entry = TimeSheetEntry {
+            description: "=SUM(A1) review".to_string(),
+            start_time: start,
+            end_time: Some(start + Duration::minutes(75)),
+            tags: vec!["dev".to_string(), "PROJ".to_string()],
+            properties: HashMap::from([
+                ("jira".to_string(), "PROJ-1".to_string()),
+                ("billable".to_string(), "true".to_string()),
+            ]),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("excel.csv");
+        let expected = [
+            (
+                "en_US.UTF-8",
+                "06/10/2025,09:00:00,06/10/2025,10:15:00,1.25,",
+            ),
+            (
+                "de_DE.UTF-8",
+                "10.06.2025;09:00:00;10.06.2025;10:15:00;1,25;",
+            ),
+            ("fr-FR", "10/06/2025;09:00:00;10/06/2025;10:15:00;1,25;"),
+            ("C", "2025-06-10,09:00:00,2025-06-10,10:15:00,1.25,"),
+        ];
+        for (tag, row) in expected {
+            write_excel_csv(&path, [&entry], &ExcelLocale::from_tag(tag));
+            let content = std::fs::read_to_string(&path).unwrap();
+            assert!(content.starts_with("\u{feff}Date"), "{tag}");
+            let line = content.lines().nth(1).unwrap();
+            assert!(line.starts_with(row), "{tag}: {line}");
+            assert!(
+                line.contains("'=SUM(A1) review") && line.contains("billable=true; jira=PROJ-1"),
+                "{tag}: {line}"
+            );
+        }
+
+        let late = TimeSheetEntry {
+            start_time: Local.with_ymd_and_hms(2025, 6, 10, 23, 30, 0).unwrap(),
+            end_time: Some(Local.with_ymd_and_hms(2025, 6, 11, 0, 30, 0).unwrap()),
+            ..entry
+        };
+        write_excel_csv(&path, [&late], &ExcelLocale::from_tag("C"));
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("2025-06-10,23:30:00,2025-06-11,00:30:00,1.00,"));
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use csv::StringRecord;
+
+use crate::{backup, read_timesheet, write_timesheet};
+
+/// Version of the csv format written by [`write_timesheet`].
+///
+/// 1. `description,start_time,end_time,tags`
+/// 2. Adds `properties`, and the version comment on the first line
+pub const CURRENT_VERSION: u32 = 2;
+
+const VERSION_PREFIX: &str = "#format_version=";
+
+/// Header and records of a timesheet, as raw strings so migrations don't depend on the current entry type.
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub headers: StringRecord,
+    pub records: Vec<StringRecord>,
+}
+
+/// Upgrades a table from the version at the same index plus one to the next.
+const MIGRATIONS: &[fn(&mut Table)] = &[v1_add_properties];
+
+fn v1_add_properties(table: &mut Table) {
+    table.headers.push_field("properties");
+    for record in table.records.iter_mut() {
+        record.push_field("");
+    }
+}
+
+pub fn version_line() -> String {
+    format!("{VERSION_PREFIX}{CURRENT_VERSION}\n")
+}
+
+fn parse_version(version: &str) -> u32 {
+    version
+        .trim()
+        .parse()
+        .expect("Timesheet format version should be a number")
+}
+
+/// Version of files written before the version comment existed, identified by their columns.
+fn version_from_columns<'a>(mut columns: impl Iterator<Item = &'a str>) -> u32 {
+    if columns.any(|h| h == "properties") {
+        2
+    } else {
+        1
+    }
+}
+
+/// Splits the version comment off the content.
+fn split_version(content: &str) -> (Option<u32>, &str) {
+    let Some(rest) = content.strip_prefix(VERSION_PREFIX) else {
+        return (None, content);
+    };
+    let (version, body) = rest.split_once('\n').unwrap_or((rest, ""));
+
+    (Some(parse_version(version)), body)
+}
+
+fn parse(content: &str) -> (u32, Table) {
+    let (version, body) = split_version(content);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b',')
+        .from_reader(body.as_bytes());
+    let headers = reader.headers().unwrap().clone();
+    let records = reader.records().map(|r| r.unwrap()).collect();
+
+    let version = version.unwrap_or_else(|| version_from_columns(headers.iter()));
+
+    (version, Table { headers, records })
+}
+
+/// Reads a timesheet in any known version, migrated to the current one.
+pub fn read_table(path: impl AsRef<Path>) -> Table {
+    let content = std::fs::read_to_string(path).unwrap();
+    let (version, mut table) = parse(&content);
+    assert!(
+        version >= 1,
+        "Timesheet format version {version} doesn't exist, versions start at 1"
+    );
+    assert!(
+        version <= CURRENT_VERSION,
+        "Timesheet format version {version} is newer than this app's {CURRENT_VERSION}"
+    );
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut table);
+    }
+
+    table
+}
+
+/// Version comment, when there is one, and header line, without reading the rest of the file.
+fn read_head(path: &Path) -> Option<(Option<String>, String)> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut lines = BufReader::new(file).lines();
+    let first = lines.next()?.ok()?;
+    let Some(version) = first.strip_prefix(VERSION_PREFIX) else {
+        return Some((None, first));
+    };
+    let header = lines.next().and_then(Result::ok).unwrap_or_default();
+
+    Some((Some(version.to_string()), header))
+}
+
+/// Whether the file starts with a timesheet header of any version, rather than being some other csv.
+pub fn is_timesheet(path: &Path) -> bool {
+    read_head(path)
+        .is_some_and(|(_, header)| header.starts_with("description,start_time,end_time,tags"))
+}
+
+fn file_version(path: &Path) -> u32 {
+    let (version, header) = read_head(path).unwrap_or_default();
+    match version {
+        Some(version) => parse_version(&version),
+        None => version_from_columns(header.split(',')),
+    }
+}
+
+/// Rewrites the timesheet at `TIMESHEET_PATH` in the current format if it is older, backing it up first.
+///
+/// Returns whether it was upgraded.
+pub fn upgrade_if_needed() -> bool {
+    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
+    let path = Path::new(&timesheet_path);
+    if !std::fs::exists(path).unwrap() {
+        return false;
+    }
+    let version = file_version(path);
+    if version >= CURRENT_VERSION {
+        return false;
+    }
+
+    backup::snapshot();
+    write_timesheet(path, &read_timesheet(path));
+    println!("[INFO] Upgraded {timesheet_path} from format version {version} to {CURRENT_VERSION}");
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_entries, lock_test_env, TimeSheetEntry};
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/formats")
+            .join(name)
+    }
+
+    #[test]
+    fn test_every_version_loads() {
+        let expected = read_timesheet(fixture("v2.csv"));
+        assert_eq!(expected.len(), 3);
+        assert_eq!(expected[2].description, "#42 Review");
+        assert_eq!(read_timesheet(fixture("v2_unversioned.csv")), expected);
+
+        let v1 = read_timesheet(fixture("v1.csv"));
+        assert!(v1.iter().all(|e| e.properties.is_empty()));
+        let without_properties = |e: &TimeSheetEntry| TimeSheetEntry {
+            properties: Default::default(),
+            ..e.clone()
+        };
+        assert_eq!(
+            v1,
+            expected.iter().map(without_properties).collect::<Vec<_>>()
+        );
+
+        for (name, version) in [("v1.csv", 1), ("v2_unversioned.csv", 2), ("v2.csv", 2)] {
+            assert_eq!(file_version(&fixture(name)), version, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_upgrade_on_load() {
+        let _env = lock_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        let timesheet_path = dir.path().join("timesheet.csv");
+        std::fs::copy(fixture("v1.csv"), &timesheet_path).unwrap();
+        std::env::set_var("TIMESHEET_PATH", &timesheet_path);
+        std::env::remove_var("BACKUP_DIR");
+
+        assert_eq!(get_entries().len(), 3);
+        assert_eq!(file_version(&timesheet_path), CURRENT_VERSION);
+        assert!(std::fs::read_to_string(&timesheet_path)
+            .unwrap()
+            .starts_with(&version_line()));
+        assert_eq!(backup::backups().len(), 1, "The old version is backed up");
+        assert!(!upgrade_if_needed());
+        assert_eq!(get_entries().len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "versions start at 1")]
+    fn test_refuses_version_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timesheet.csv");
+        std::fs::write(
+            &path,
+            format!("{VERSION_PREFIX}0\ndescription,start_time,end_time,tags,properties\n"),
+        )
+        .unwrap();
+        read_table(&path);
+    }
+
+    #[test]
+    #[should_panic(expected = "newer than this app")]
+    fn test_refuses_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timesheet.csv");
+        std::fs::write(
+            &path,
+            format!("{VERSION_PREFIX}99\ndescription,start_time,end_time,tags,properties\n"),
+        )
+        .unwrap();
+        read_table(&path);
+    }
+}
@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::cache::EntryCache;
+use crate::{parse_date, TimeSheetEntry};
+
+const PRODID: &str = "-//local-timesheet//EN";
+/// Prefix of the X- fields carrying entry properties
+const PROPERTY_PREFIX: &str = "X-TIMESHEET-";
+/// Content lines longer than this many octets get folded
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Escapes text values: backslashes, separators and newlines.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Splits a content line into CRLF terminated lines of at most 75 octets,
+/// continuation lines starting with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn format_utc(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// `jira_worklog_id` becomes `X-TIMESHEET-JIRA-WORKLOG-ID`, names only allowing letters, digits and dashes.
+fn property_name(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{PROPERTY_PREFIX}{key}")
+}
+
+/// FNV-1a, stable across builds unlike the std hasher, so UIDs stay the same between exports.
+fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Same entry, same UID, so calendars update events when exporting again.
+fn uid(entry: &TimeSheetEntry) -> String {
+    format!(
+        "{}-{:016x}@local-timesheet",
+        entry.start_time.timestamp_millis(),
+        stable_hash(&entry.description)
+    )
+}
+
+fn to_vevent(entry: &TimeSheetEntry, stamp: &str) -> Option<String> {
+    let end_time = entry.end_time?;
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid(entry)),
+        format!("DTSTAMP:{stamp}"),
+        format!("DTSTART:{}", format_utc(entry.start_time)),
+        format!("DTEND:{}", format_utc(end_time)),
+        format!("SUMMARY:{}", escape(&entry.description)),
+    ];
+    if !entry.tags.is_empty() {
+        let tags: Vec<String> = entry.tags.iter().map(|t| escape(t)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    let mut properties: Vec<_> = entry.properties.iter().collect();
+    properties.sort();
+    for (key, value) in properties {
+        lines.push(format!("{}:{}", property_name(key), escape(value)));
+    }
+    lines.push("END:VEVENT".to_string());
+
+    Some(lines.iter().map(|l| fold(l)).collect())
+}
+
+/// Calendar with one event per finished entry, running ones having no end yet.
+pub fn to_ics<'a>(entries: impl IntoIterator<Item = &'a TimeSheetEntry>) -> (String, usize) {
+    let stamp = format_utc(Local::now());
+    let mut calendar = [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        &format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN",
+    ]
+    .iter()
+    .map(|l| fold(l))
+    .collect::<String>();
+    let mut count = 0;
+    for event in entries.into_iter().filter_map(|e| to_vevent(e, &stamp)) {
+        calendar.push_str(&event);
+        count += 1;
+    }
+    calendar.push_str(&fold("END:VCALENDAR"));
+
+    (calendar, count)
+}
+
+pub fn write_ics<'a>(
+    path: impl AsRef<Path>,
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+) -> usize {
+    let (calendar, count) = to_ics(entries);
+    std::fs::write(path, calendar).unwrap();
+    count
+}
+
+/// Writes the finished entries started between the two dates, included, to an `.ics` file.
+#[tauri::command]
+pub fn export_ics(cache: &EntryCache, path: String, from: &str, to: &str) -> Result<usize, String> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+
+    let entries = cache.in_range(from..=to);
+    let count = write_ics(&path, &entries);
+    println!("[INFO] Exported {count} entries to {path}");
+
+    Ok(count)
+}
+
+/// Outlook's Windows time zone names, with the IANA zone of their main region.
+const WINDOWS_TIME_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("US Eastern Standard Time", "America/Indiana/Indianapolis"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("Argentina Standard Time", "America/Argentina/Buenos_Aires"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Greenland Standard Time", "America/Nuuk"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("FLE Standard Time", "Europe/Kyiv"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("India Standard Time", "Asia/Kolkata"),
+    ("Nepal Standard Time", "Asia/Kathmandu"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+];
+
+/// Event read from a calendar, see [`parse_ics`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    /// UID, unique together with the start for occurrences of recurring events
+    pub uid: String,
+    pub summary: String,
+    /// Common name or address of the organizer
+    pub organizer: Option<String>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub categories: Vec<String>,
+    /// `X-TIMESHEET-` fields, as written by [`to_ics`]
+    pub properties: HashMap<String, String>,
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    unescaped
+}
+
+/// Splits on the separator where it isn't escaped or quoted.
+fn split_unescaped(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut escaped, mut quoted) = (0, false, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Event left out of an import because it can't be read as time spent, and why.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedEvent {
+    pub summary: String,
+    pub reason: String,
+}
+
+/// Name, parameters and value of a content line.
+type Field<'a> = (String, HashMap<String, String>, &'a str);
+
+/// Parses a content line like `DTSTART;TZID=Europe/Paris:20250610T090000`.
+fn parse_line(line: &str) -> Option<Field<'_>> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        (c == ':' && !quoted).then_some(i)
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = split_unescaped(head, ';').into_iter();
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_ascii_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+
+    Some((name, params, value))
+}
+
+/// IANA name, Windows name as written by Outlook, or a path ending with the IANA name
+/// like `/freeassociation.sourceforge.net/Europe/Paris`.
+fn resolve_time_zone(tzid: &str) -> Option<Tz> {
+    if let Ok(tz) = tzid.parse() {
+        return Some(tz);
+    }
+    if let Some((_, iana)) = WINDOWS_TIME_ZONES.iter().find(|(name, _)| *name == tzid) {
+        return iana.parse().ok();
+    }
+    tzid.match_indices('/')
+        .find_map(|(i, _)| tzid[i + 1..].parse().ok())
+}
+
+/// UTC times end with `Z`, others are in their `TZID`, or floating and taken as local time.
+/// All-day dates give `None`, they aren't time spent.
+fn parse_datetime(
+    value: &str,
+    params: &HashMap<String, String>,
+) -> Result<Option<DateTime<Local>>, String> {
+    if params.get("VALUE").is_some_and(|v| v == "DATE") {
+        return Ok(None);
+    }
+    let parse = |value| {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .map_err(|e| format!("invalid date {value}: {e}"))
+    };
+    if let Some(utc) = value.strip_suffix('Z') {
+        return Ok(Some(parse(utc)?.and_utc().with_timezone(&Local)));
+    }
+
+    let time = parse(value)?;
+    let time = match params.get("TZID") {
+        Some(tzid) => {
+            let tz = resolve_time_zone(tzid).ok_or(format!("unknown time zone {tzid}"))?;
+            time.and_local_timezone(tz)
+                .earliest()
+                .map(|t| t.with_timezone(&Local))
+        }
+        None => time.and_local_timezone(Local).earliest(),
+    };
+
+    time.map(Some)
+        .ok_or(format!("{value} doesn't exist in its time zone"))
+}
+
+/// `PT1H30M`, `P1D`, `P1W`...
+fn parse_ics_duration(value: &str) -> Option<Duration> {
+    let value = value.strip_prefix('+').unwrap_or(value);
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = r;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        total += match (rest[digits..].chars().next()?, in_time) {
+            ('W', false) => Duration::weeks(amount),
+            ('D', false) => Duration::days(amount),
+            ('H', true) => Duration::hours(amount),
+            ('M', true) => Duration::minutes(amount),
+            ('S', true) => Duration::seconds(amount),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(total)
+}
+
+/// Timed events of a calendar, leaving out all-day and cancelled ones.
+///
+/// Recurring events are skipped and returned with the reason, rather than imported
+/// as their first occurrence. Modified occurrences sent as events of their own are read.
+pub fn parse_ics(content: &str) -> (Vec<CalendarEvent>, Vec<SkippedEvent>) {
+    let unfolded = content
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut skipped = Vec::new();
+    let mut current: Option<Vec<Field>> = None;
+    for line in unfolded.lines() {
+        let Some((name, params, value)) = parse_line(line) else {
+            continue;
+        };
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                let Some(fields) = current.take() else {
+                    continue;
+                };
+                match to_event(&fields) {
+                    Ok(event) => events.extend(event),
+                    Err(reason) => skipped.push(SkippedEvent {
+                        summary: fields
+                            .iter()
+                            .find(|(n, _, _)| n == "SUMMARY")
+                            .map(|(_, _, v)| unescape(v))
+                            .unwrap_or_default(),
+                        reason,
+                    }),
+                }
+            }
+            _ => {
+                if let Some(fields) = current.as_mut() {
+                    fields.push((name, params, value));
+                }
+            }
+        }
+    }
+
+    (events, skipped)
+}
+
+/// The event, `None` when it isn't time spent, or why it can't be imported.
+fn to_event(fields: &[Field]) -> Result<Option<CalendarEvent>, String> {
+    let field = |name: &str| fields.iter().find(|(n, _, _)| n == name);
+    if field("STATUS").is_some_and(|(_, _, v)| v.eq_ignore_ascii_case("CANCELLED")) {
+        return Ok(None);
+    }
+
+    let (_, params, value) = field("DTSTART").ok_or("no start")?;
+    let Some(start) = parse_datetime(value, params)? else {
+        return Ok(None);
+    };
+    if field("RRULE").is_some() || field("RDATE").is_some() {
+        return Err("recurring".to_string());
+    }
+    let end = match field("DTEND") {
+        Some((_, params, value)) => parse_datetime(value, params)?.ok_or("all-day end")?,
+        None => {
+            let duration = field("DURATION").ok_or("no end or duration")?;
+            start + parse_ics_duration(duration.2).ok_or("invalid duration")?
+        }
+    };
+
+    let organizer = field("ORGANIZER").map(|(_, params, value)| {
+        params.get("CN").cloned().unwrap_or_else(|| {
+            let address = value.strip_prefix("mailto:").unwrap_or(value);
+            address
+                .strip_prefix("MAILTO:")
+                .unwrap_or(address)
+                .to_string()
+        })
+    });
+    let categories = fields
+        .iter()
+        .filter(|(n, _, _)| n == "CATEGORIES")
+        .flat_map(|(_, _, v)| split_unescaped(v, ','))
+        .map(|c| unescape(c.trim()))
+        .filter(|c| !c.is_empty())
+        .collect();
+    let properties = fields
+        .iter()
+        .filter_map(|(name, _, value)| {
+            let key = name.strip_prefix(PROPERTY_PREFIX)?;
+            Some((key.to_ascii_lowercase().replace('-', "_"), unescape(value)))
+        })
+        .collect();
+
+    Ok(Some(CalendarEvent {
+        uid: field("UID")
+            .map(|(_, _, v)| v.to_string())
+            .unwrap_or_default(),
+        summary: field("SUMMARY")
+            .map(|(_, _, v)| unescape(v))
+            .unwrap_or_default(),
+        organizer,
+        start,
+        end,
+        categories,
+        properties,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_export() {
+        let start = Utc
+            .with_ymd_and_hms(2025, 6, 10, 7, 0, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let finished = TimeSheetEntry {
+            description: "Planning; budgets, \"Q3\" and a description long enough to need folding"
+                .to_string(),
+            start_time: start,
+            end_time: Some(start + Duration::minutes(90)),
+            tags: vec!["meeting".to_string(), "PROJ".to_string()],
+            properties: HashMap::from([
+                ("jira".to_string(), "PROJ-1".to_string()),
+                ("jira_worklog_id".to_string(), "10001".to_string()),
+            ]),
+        };
+        let running = TimeSheetEntry {
+            description: "Running".to_string(),
+            start_time: start + Duration::hours(2),
+            end_time: None,
+            tags: vec![],
+            properties: HashMap::new(),
+        };
+
+        let (calendar, count) = to_ics([&finished, &running]);
+        assert_eq!(count, 1);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(calendar.split("\r\n").all(|l| l.len() <= MAX_LINE_OCTETS));
+
+        let unfolded = calendar.replace("\r\n ", "");
+        let lines: Vec<&str> = unfolded.split("\r\n").collect();
+        for expected in [
+            "DTSTART:20250610T070000Z",
+            "DTEND:20250610T083000Z",
+            "SUMMARY:Planning\\; budgets\\, \"Q3\" and a description long enough to need folding",
+            "CATEGORIES:meeting,PROJ",
+            "X-TIMESHEET-JIRA:PROJ-1",
+            "X-TIMESHEET-JIRA-WORKLOG-ID:10001",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "{expected} missing from {unfolded}"
+            );
+        }
+
+        let (again, _) = to_ics([&finished]);
+        let uid = |calendar: &str| {
+            calendar
+                .lines()
+                .find(|l| l.starts_with("UID:"))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(uid(&calendar), uid(&again));
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:abc@example.com\r\n\
+            SUMMARY:Sprint planning\\, team \r\n A\r\n\
+            ORGANIZER;CN=\"Doe: Jane\":mailto:jane@example.com\r\n\
+            DTSTART;TZID=Europe/Paris:20250610T090000\r\n\
+            DURATION:PT1H30M\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:From Outlook\r\n\
+            DTSTART;TZID=Eastern Standard Time:20250610T090000\r\n\
+            DTEND;TZID=Eastern Standard Time:20250610T093000\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Holiday\r\n\
+            DTSTART;VALUE=DATE:20250611\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Cancelled\r\n\
+            STATUS:CANCELLED\r\n\
+            DTSTART:20250612T090000Z\r\n\
+            DTEND:20250612T100000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Standup\r\n\
+            DTSTART;TZID=Europe/Paris:20230102T093000\r\n\
+            DTEND;TZID=Europe/Paris:20230102T094500\r\n\
+            RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Somewhere\r\n\
+            DTSTART;TZID=Middle Earth:20250613T090000\r\n\
+            DTEND;TZID=Middle Earth:20250613T100000\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let (events, skipped) = parse_ics(content);
+        assert_eq!(events.len(), 2);
+        let start = Utc.with_ymd_and_hms(2025, 6, 10, 7, 0, 0).unwrap();
+        assert_eq!(events[0].summary, "Sprint planning, team A");
+        assert_eq!(events[0].organizer.as_deref(), Some("Doe: Jane"));
+        assert_eq!(
+            (events[0].start, events[0].end),
+            (start.into(), (start + Duration::minutes(90)).into())
+        );
+        assert_eq!(
+            events[1].start,
+            Utc.with_ymd_and_hms(2025, 6, 10, 13, 0, 0).unwrap()
+        );
+
+        let reasons: Vec<(&str, &str)> = skipped
+            .iter()
+            .map(|s| (s.summary.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("Standup", "recurring"),
+                ("Somewhere", "unknown time zone Middle Earth")
+            ]
+        );
+        assert_eq!(parse_ics_duration("P1DT2H"), Some(Duration::hours(26)));
+    }
+
+    #[test]
+    fn test_time_zone_names() {
+        for (windows, iana) in WINDOWS_TIME_ZONES {
+            assert!(resolve_time_zone(windows).is_some(), "{windows}: {iana}");
+        }
+        assert_eq!(
+            resolve_time_zone("/freeassociation.sourceforge.net/Europe/Paris"),
+            Some(chrono_tz::Europe::Paris)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let start = Utc
+            .with_ymd_and_hms(2025, 6, 10, 7, 0, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let entry = TimeSheetEntry {
+            description: "Review; \"fixes\", and notes\nover two lines".to_string(),
+            start_time: start,
+            end_time: Some(start + Duration::minutes(45)),
+            tags: vec!["dev".to_string(), "a,b".to_string()],
+            properties: HashMap::from([("jira_worklog_id".to_string(), "10001".to_string())]),
+        };
+
+        let (events, _) = parse_ics(&to_ics([&entry]).0);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, entry.description);
+        assert_eq!(events[0].start, entry.start_time);
+        assert_eq!(Some(events[0].end), entry.end_time);
+        assert_eq!(events[0].categories, entry.tags);
+        assert_eq!(events[0].properties, entry.properties);
+        assert_eq!(events[0].uid, uid(&entry));
+    }
+}
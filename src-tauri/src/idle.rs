@@ -0,0 +1,373 @@
+use std::sync::Mutex;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::cache::EntryCache;
+use crate::journal::{self, Operation};
+use crate::reminders::{Clock, SystemClock};
+use crate::{write_entries, TimeSheetEntry};
+
+/// Event emitted to the frontend when the user is back after being idle with a timer running.
+pub const IDLE_RETURN_EVENT: &str = "idle-returned";
+
+/// How often the idle time is read.
+const POLL_INTERVAL: StdDuration = StdDuration::from_secs(30);
+
+/// Time since the user last used the keyboard or mouse, `None` when it can't be told.
+pub trait IdleSource: Send {
+    fn idle_time(&self) -> Option<Duration>;
+}
+
+/// `xprintidle` on X11, or the idle hint logind gets from the desktop, Wayland ones included.
+#[cfg(target_os = "linux")]
+pub struct LinuxIdleSource;
+
+#[cfg(target_os = "linux")]
+impl IdleSource for LinuxIdleSource {
+    fn idle_time(&self) -> Option<Duration> {
+        // Under XWayland, xprintidle only sees the input going to X11 windows
+        if is_wayland_session() {
+            logind_idle_time().or_else(xprintidle)
+        } else {
+            xprintidle().or_else(logind_idle_time)
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland")
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+#[cfg(target_os = "linux")]
+fn xprintidle() -> Option<Duration> {
+    let output = std::process::Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let millis = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::milliseconds(millis))
+}
+
+#[cfg(target_os = "linux")]
+fn logind_idle_time() -> Option<Duration> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = std::process::Command::new("loginctl")
+        .args([
+            "show-session",
+            &session,
+            "--property=IdleHint",
+            "--property=IdleSinceHint",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_logind(&String::from_utf8_lossy(&output.stdout), Local::now())
+}
+
+/// Reads `IdleHint=yes` and `IdleSinceHint=<microseconds since the epoch>` from `loginctl show-session`.
+#[cfg(target_os = "linux")]
+fn parse_logind(output: &str, now: DateTime<Local>) -> Option<Duration> {
+    let property = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+    };
+    if property("IdleHint")? != "yes" {
+        return Some(Duration::zero());
+    }
+    let since = DateTime::from_timestamp_micros(property("IdleSinceHint")?.parse().ok()?)?;
+
+    Some(now.to_utc() - since)
+}
+
+/// Idle time taken out of the running entry, or not, once the user is back.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IdlePeriod {
+    /// Start of the entry that was running
+    pub entry_start: DateTime<Local>,
+    pub description: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    /// Stops the entry when the user left, and resumes it when they came back
+    Discard,
+    Keep,
+    /// Like discard, with an `idle` entry for the time away to reassign later
+    Split,
+}
+
+/// Notices when the user is back after being idle for longer than the threshold.
+pub struct IdleDetector<S: IdleSource, C: Clock> {
+    source: S,
+    clock: C,
+    threshold: Duration,
+    idle_since: Option<DateTime<Local>>,
+}
+
+impl<S: IdleSource, C: Clock> IdleDetector<S, C> {
+    pub fn new(source: S, clock: C, threshold: Duration) -> Self {
+        IdleDetector {
+            source,
+            clock,
+            threshold,
+            idle_since: None,
+        }
+    }
+
+    /// The idle period the user just came back from, when an entry was running through it.
+    pub fn poll(&mut self, entries: &[TimeSheetEntry]) -> Option<IdlePeriod> {
+        let idle = self.source.idle_time()?;
+        let now = self.clock.now();
+        if idle >= self.threshold {
+            self.idle_since.get_or_insert(now - idle);
+            return None;
+        }
+
+        let start = self.idle_since.take()?;
+        let end = now - idle;
+        let running = entries
+            .iter()
+            .find(|e| e.end_time.is_none() && e.start_time < end)?;
+
+        Some(IdlePeriod {
+            entry_start: running.start_time,
+            description: running.description.clone(),
+            start: start.max(running.start_time),
+            end,
+        })
+    }
+}
+
+/// Changes taking the idle period out of its entry.
+///
+/// The entry may have been stopped since, it is then only resumed if it ended after the user came back.
+pub fn resolve(
+    entries: &[TimeSheetEntry],
+    period: &IdlePeriod,
+    action: IdleAction,
+) -> Option<Vec<Operation>> {
+    if action == IdleAction::Keep {
+        return Some(Vec::new());
+    }
+    let old = entries
+        .iter()
+        .find(|e| e.start_time == period.entry_start && e.description == period.description)?;
+
+    let mut operations = vec![Operation::Update {
+        old: old.clone(),
+        new: TimeSheetEntry {
+            end_time: Some(period.start),
+            ..old.clone()
+        },
+    }];
+    if action == IdleAction::Split {
+        operations.push(Operation::Add {
+            entry: TimeSheetEntry {
+                description: "Idle".to_string(),
+                start_time: period.start,
+                end_time: Some(period.end),
+                tags: vec!["idle".to_string()],
+                properties: Default::default(),
+            },
+        });
+    }
+    if old.end_time.is_none_or(|end| end > period.end) {
+        operations.push(Operation::Add {
+            entry: TimeSheetEntry {
+                start_time: period.end,
+                // Worklogs belong to the part already logged
+                properties: old
+                    .properties
+                    .iter()
+                    .filter(|(k, _)| *k != "jira_worklog_id" && *k != "jira_time_spent")
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+                ..old.clone()
+            },
+        });
+    }
+
+    Some(operations)
+}
+
+/// Idle period waiting for the user to choose what to do with it.
+static PENDING: Mutex<Option<IdlePeriod>> = Mutex::new(None);
+
+/// Polls the idle time in the background when `IDLE_THRESHOLD_MINUTES` is set,
+/// emitting [`IDLE_RETURN_EVENT`] when the user is back.
+#[cfg(target_os = "linux")]
+pub fn start(app: AppHandle) {
+    let Ok(minutes) = std::env::var("IDLE_THRESHOLD_MINUTES") else {
+        return;
+    };
+    let minutes: i64 = minutes
+        .parse()
+        .expect("IDLE_THRESHOLD_MINUTES should be a number of minutes");
+    let mut detector = IdleDetector::new(LinuxIdleSource, SystemClock, Duration::minutes(minutes));
+    std::thread::spawn(move || loop {
+        if let Some(period) = detector.poll(&app.state::<EntryCache>().entries()) {
+            println!(
+                "[INFO] Idle from {} to {} on {}",
+                period.start, period.end, period.description
+            );
+            *PENDING.lock().unwrap() = Some(period.clone());
+            if let Err(e) = app.emit(IDLE_RETURN_EVENT, period) {
+                eprintln!("[ERROR] Failed to emit {IDLE_RETURN_EVENT}: {e}");
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn start(_app: AppHandle) {}
+
+#[tauri::command]
+pub fn get_idle_period() -> Option<IdlePeriod> {
+    PENDING.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn resolve_idle(cache: &EntryCache, action: IdleAction) -> bool {
+    let Some(period) = PENDING.lock().unwrap().take() else {
+        return false;
+    };
+    let mut entries = cache.entries().to_vec();
+    let Some(operations) = resolve(&entries, &period, action) else {
+        println!("[INFO] Entry idle from {} is gone", period.start);
+        return false;
+    };
+    if operations.is_empty() {
+        return true;
+    }
+
+    for operation in operations.iter() {
+        operation.apply(&mut entries).unwrap();
+    }
+    write_entries(cache, &entries);
+    journal::record_all(operations);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct Fake(Arc<Mutex<(DateTime<Local>, Duration)>>);
+
+    impl Clock for Fake {
+        fn now(&self) -> DateTime<Local> {
+            self.0.lock().unwrap().0
+        }
+    }
+
+    impl IdleSource for Fake {
+        fn idle_time(&self) -> Option<Duration> {
+            Some(self.0.lock().unwrap().1)
+        }
+    }
+
+    impl Fake {
+        fn set(&self, hour: u32, minute: u32, idle_minutes: i64) {
+            *self.0.lock().unwrap() = (at(hour, minute), Duration::minutes(idle_minutes));
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2025, 6, 10, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_detect_and_resolve() {
+        let fake = Fake(Arc::new(Mutex::new((at(9, 0), Duration::zero()))));
+        let mut detector = IdleDetector::new(fake.clone(), fake.clone(), Duration::minutes(10));
+        let entries = vec![TimeSheetEntry {
+            description: "Review".to_string(),
+            start_time: at(9, 0),
+            end_time: None,
+            tags: vec!["dev".to_string()],
+            properties: Default::default(),
+        }];
+
+        for (hour, minute, idle) in [(9, 30, 0), (9, 45, 5), (10, 0, 20), (10, 30, 50)] {
+            fake.set(hour, minute, idle);
+            assert_eq!(detector.poll(&entries), None);
+        }
+        fake.set(10, 31, 0);
+        let period = detector.poll(&entries).unwrap();
+        assert_eq!((period.start, period.end), (at(9, 40), at(10, 31)));
+        fake.set(10, 32, 0);
+        assert_eq!(detector.poll(&entries), None, "Reported once");
+
+        let apply = |action| {
+            let mut entries = entries.clone();
+            for operation in resolve(&entries, &period, action).unwrap() {
+                operation.apply(&mut entries).unwrap();
+            }
+            entries
+                .iter()
+                .map(|e| (e.description.clone(), e.start_time, e.end_time))
+                .collect::<Vec<_>>()
+        };
+        let review = |start, end| ("Review".to_string(), start, end);
+        assert_eq!(apply(IdleAction::Keep), [review(at(9, 0), None)]);
+        assert_eq!(
+            apply(IdleAction::Discard),
+            [review(at(9, 0), Some(at(9, 40))), review(at(10, 31), None)]
+        );
+        assert_eq!(
+            apply(IdleAction::Split),
+            [
+                review(at(9, 0), Some(at(9, 40))),
+                ("Idle".to_string(), at(9, 40), Some(at(10, 31))),
+                review(at(10, 31), None)
+            ]
+        );
+
+        let mut stopped = entries.clone();
+        stopped[0].end_time = Some(at(10, 0));
+        assert_eq!(
+            resolve(&stopped, &period, IdleAction::Discard)
+                .unwrap()
+                .len(),
+            1,
+            "Stopped while away, so not resumed"
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_logind() {
+        let now = at(10, 0);
+        let since = (now - Duration::minutes(12)).timestamp_micros();
+        assert_eq!(
+            parse_logind(&format!("IdleHint=yes\nIdleSinceHint={since}\n"), now),
+            Some(Duration::minutes(12))
+        );
+        assert_eq!(
+            parse_logind("IdleHint=no\nIdleSinceHint=0\n", now),
+            Some(Duration::zero())
+        );
+        assert_eq!(parse_logind("", now), None);
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::{toggl, write_entries, TimeSheetEntry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationFormat {
+    /// `H:MM` or `H:MM:SS`
+    Clock,
+    /// `1.5` for an hour and a half
+    DecimalHours,
+}
+
+/// Where to find each field of an entry in another tracker's csv export.
+///
+/// Columns are referred to by header name. The start time column can be left out when the date
+/// column holds both, or for trackers only recording hours per day, whose entries are then laid
+/// out back to back from `day_start`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub delimiter: char,
+    /// First non-empty column is used
+    pub description: Vec<String>,
+    pub start_date: String,
+    pub start_time: Option<String>,
+    pub end_date: Option<String>,
+    pub end_time: Option<String>,
+    /// Used when there is no end
+    pub duration: Option<String>,
+    pub duration_format: DurationFormat,
+    /// chrono format, e.g. `%Y-%m-%d`
+    pub date_format: String,
+    /// chrono format, e.g. `%H:%M:%S`
+    pub time_format: String,
+    pub day_start: NaiveTime,
+    pub tags: Option<String>,
+    pub tag_separator: String,
+    pub project: Option<String>,
+    /// Yes/No or true/false column, stored as the `billable` property
+    pub billable: Option<String>,
+    /// Other columns to keep, from column name to property key
+    pub properties: HashMap<String, String>,
+}
+
+/// Toggl's detailed report, the layout `TOGGL_SHEET_PATH` is read with.
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            description: vec!["Description".to_string()],
+            start_date: "Start date".to_string(),
+            start_time: Some("Start time".to_string()),
+            end_date: Some("End date".to_string()),
+            end_time: Some("End time".to_string()),
+            duration: Some("Duration".to_string()),
+            duration_format: DurationFormat::Clock,
+            date_format: "%Y-%m-%d".to_string(),
+            time_format: "%H:%M:%S".to_string(),
+            day_start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            tags: Some("Tags".to_string()),
+            tag_separator: ",".to_string(),
+            project: Some("Project".to_string()),
+            billable: Some("Billable".to_string()),
+            properties: HashMap::from([
+                ("Client".to_string(), "client".to_string()),
+                ("Email".to_string(), "email".to_string()),
+                ("Task".to_string(), "task".to_string()),
+            ]),
+        }
+    }
+}
+
+/// Mappings for the exports of trackers colleagues migrate from, by name.
+#[tauri::command]
+pub fn get_import_presets() -> HashMap<String, ColumnMapping> {
+    let toggl = ColumnMapping::default();
+    // Detailed report, with the default US date and 12 hour time formats
+    let clockify = ColumnMapping {
+        start_date: "Start Date".to_string(),
+        start_time: Some("Start Time".to_string()),
+        end_date: Some("End Date".to_string()),
+        end_time: Some("End Time".to_string()),
+        duration: Some("Duration (h)".to_string()),
+        date_format: "%m/%d/%Y".to_string(),
+        time_format: "%I:%M:%S %p".to_string(),
+        ..toggl.clone()
+    };
+    // Detailed time report, which only has hours per day
+    let harvest = ColumnMapping {
+        description: vec!["Notes".to_string(), "Task".to_string()],
+        start_date: "Date".to_string(),
+        start_time: None,
+        end_date: None,
+        end_time: None,
+        duration: Some("Hours".to_string()),
+        duration_format: DurationFormat::DecimalHours,
+        tags: None,
+        billable: Some("Billable?".to_string()),
+        properties: HashMap::from([
+            ("Client".to_string(), "client".to_string()),
+            ("Task".to_string(), "task".to_string()),
+            ("Project Code".to_string(), "project_code".to_string()),
+        ]),
+        ..toggl.clone()
+    };
+
+    HashMap::from([
+        ("toggl".to_string(), toggl),
+        ("clockify".to_string(), clockify),
+        ("harvest".to_string(), harvest),
+    ])
+}
+
+pub(crate) fn is_billable(value: &str) -> bool {
+    ["yes", "true", "1"]
+        .iter()
+        .any(|b| value.trim().eq_ignore_ascii_case(b))
+}
+
+fn parse_duration(value: &str, format: DurationFormat) -> Option<Duration> {
+    match format {
+        DurationFormat::Clock => {
+            toggl::parse_duration(value).or_else(|| toggl::parse_duration(&format!("{value}:00")))
+        }
+        DurationFormat::DecimalHours => {
+            let hours: f64 = value.trim().replace(',', ".").parse().ok()?;
+            Some(Duration::seconds((hours * 3600.0).round() as i64))
+        }
+    }
+}
+
+fn to_local(naive: NaiveDateTime) -> Result<DateTime<Local>, String> {
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| format!("{naive} doesn't exist in the local time zone"))
+}
+
+struct Row<'a> {
+    headers: &'a StringRecord,
+    record: &'a StringRecord,
+}
+
+impl Row<'_> {
+    /// Non-empty value of the column.
+    fn get(&self, column: &str) -> Option<&str> {
+        let index = self.headers.iter().position(|h| h.trim() == column)?;
+        self.record
+            .get(index)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    }
+
+    fn require(&self, column: &str) -> Result<&str, String> {
+        self.get(column).ok_or_else(|| format!("Missing {column}"))
+    }
+}
+
+/// Converts one row, `day_ends` tracking where the previous entry of each day ended
+/// for exports without start times.
+fn to_entry(
+    row: &Row,
+    mapping: &ColumnMapping,
+    day_ends: &mut HashMap<NaiveDate, DateTime<Local>>,
+) -> Result<TimeSheetEntry, String> {
+    let datetime_format = format!("{} {}", mapping.date_format, mapping.time_format);
+    let parse_date = |date: &str| {
+        NaiveDate::parse_from_str(date, &mapping.date_format)
+            .map_err(|e| format!("Invalid date {date}: {e}"))
+    };
+    let duration = || {
+        let column = mapping.duration.as_deref()?;
+        parse_duration(row.get(column)?, mapping.duration_format)
+    };
+
+    let date = row.require(&mapping.start_date)?;
+    let stacked = mapping.start_time.is_none()
+        && NaiveDateTime::parse_from_str(date, &mapping.date_format).is_err();
+    let start_time = match &mapping.start_time {
+        Some(column) => {
+            let time = row.require(column)?;
+            NaiveDateTime::parse_from_str(&format!("{date} {time}"), &datetime_format)
+                .map_err(|e| format!("Invalid start {date} {time}: {e}"))?
+        }
+        None if !stacked => NaiveDateTime::parse_from_str(date, &mapping.date_format).unwrap(),
+        None => {
+            let day = parse_date(date)?;
+            match day_ends.get(&day) {
+                Some(end) => end.naive_local(),
+                None => day.and_time(mapping.day_start),
+            }
+        }
+    };
+    let start_time = to_local(start_time)?;
+
+    let end_time = match mapping.end_time.as_deref().and_then(|c| row.get(c)) {
+        Some(time) => {
+            let date = match mapping.end_date.as_deref().and_then(|c| row.get(c)) {
+                Some(date) => parse_date(date)?,
+                None => start_time.date_naive(),
+            };
+            let time = NaiveTime::parse_from_str(time, &mapping.time_format)
+                .map_err(|e| format!("Invalid end time {time}: {e}"))?;
+            let mut end_time = to_local(date.and_time(time))?;
+            // Only the time was given and the entry went past midnight
+            if end_time < start_time {
+                end_time += Duration::days(1);
+            }
+            end_time
+        }
+        None => start_time + duration().ok_or("Missing end and duration")?,
+    };
+    if stacked {
+        day_ends.insert(start_time.date_naive(), end_time);
+    }
+
+    let description = mapping
+        .description
+        .iter()
+        .find_map(|c| row.get(c))
+        .unwrap_or_default()
+        .to_string();
+    let tags = match mapping.tags.as_deref().and_then(|c| row.get(c)) {
+        Some(tags) => tags
+            .split(mapping.tag_separator.as_str())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut properties = HashMap::new();
+    if let Some(project) = mapping.project.as_deref().and_then(|c| row.get(c)) {
+        properties.insert("project".to_string(), project.to_string());
+    }
+    if let Some(billable) = mapping.billable.as_deref().and_then(|c| row.get(c)) {
+        properties.insert("billable".to_string(), is_billable(billable).to_string());
+    }
+    for (column, key) in mapping.properties.iter() {
+        if let Some(value) = row.get(column) {
+            properties.insert(key.clone(), value.to_string());
+        }
+    }
+
+    Ok(TimeSheetEntry {
+        description,
+        start_time,
+        end_time: Some(end_time),
+        tags,
+        properties,
+    })
+}
+
+/// Reads every row of the export, with an error message for the rows that can't be converted.
+pub fn read_csv(
+    content: &str,
+    mapping: &ColumnMapping,
+) -> Result<Vec<Result<TimeSheetEntry, String>>, String> {
+    // The csv reader splits on a single byte
+    if !mapping.delimiter.is_ascii() {
+        return Err(format!(
+            "Delimiter {} should be an ASCII character",
+            mapping.delimiter
+        ));
+    }
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(mapping.delimiter as u8)
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    let mut day_ends = HashMap::new();
+    let rows = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record.map_err(|e| format!("Row {}: {e}", i + 1))?;
+            let row = Row {
+                headers: &headers,
+                record: &record,
+            };
+            to_entry(&row, mapping, &mut day_ends).map_err(|e| format!("Row {}: {e}", i + 1))
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+    /// New entries added to the timesheet
+    pub imported: usize,
+    /// Entries already in the timesheet, or earlier in the file
+    pub duplicates: usize,
+    /// Duplicates that got properties they were missing, like ones added from `TOGGL_SHEET_PATH`
+    pub enriched: usize,
+}
+
+/// Exports usually only have second precision, and descriptions may have been trimmed on either side,
+/// so entries are the same when these match.
+fn entry_key(entry: &TimeSheetEntry) -> (i64, String) {
+    (
+        entry.start_time.timestamp(),
+        entry.description.trim().to_string(),
+    )
+}
+
+/// Adds the imported entries missing from `entries`, completing the properties of those already there.
+pub fn merge(entries: &mut Vec<TimeSheetEntry>, imported: Vec<TimeSheetEntry>) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    let mut indices: HashMap<(i64, String), usize> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        indices.entry(entry_key(entry)).or_insert(i);
+    }
+    for entry in imported {
+        match indices.entry(entry_key(&entry)) {
+            Entry::Occupied(index) => {
+                let existing = &mut entries[*index.get()];
+                summary.duplicates += 1;
+                let mut enriched = false;
+                for (key, value) in entry.properties {
+                    if let Entry::Vacant(property) = existing.properties.entry(key) {
+                        property.insert(value);
+                        enriched = true;
+                    }
+                }
+                if enriched {
+                    summary.enriched += 1;
+                }
+            }
+            Entry::Vacant(index) => {
+                index.insert(entries.len());
+                entries.push(entry);
+                summary.imported += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportPreview {
+    #[serde(flatten)]
+    pub summary: ImportSummary,
+    /// Entries that are or would be added
+    pub entries: Vec<TimeSheetEntry>,
+    pub errors: Vec<String>,
+}
+
+/// Imports another tracker's csv export, or only previews it with `dry_run`.
+///
+/// Nothing is written if any row can't be converted.
+#[tauri::command]
+pub fn import_csv(
+    cache: &EntryCache,
+    path: String,
+    mapping: ColumnMapping,
+    dry_run: bool,
+) -> Result<ImportPreview, String> {
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Can't read {path}: {e}"))?;
+    let (converted, errors): (Vec<_>, Vec<_>) = read_csv(&content, &mapping)?
+        .into_iter()
+        .partition(|r| r.is_ok());
+    let converted: Vec<TimeSheetEntry> = converted.into_iter().map(Result::unwrap).collect();
+    let errors: Vec<String> = errors.into_iter().map(Result::unwrap_err).collect();
+
+    let mut entries = cache.entries().to_vec();
+    let existing = entries.len();
+    let summary = merge(&mut entries, converted);
+    let added = entries[existing..].to_vec();
+
+    if !dry_run {
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        if summary.imported > 0 || summary.enriched > 0 {
+            write_entries(cache, &entries);
+        }
+        println!(
+            "[INFO] Imported {} entries from {path}, skipped {} duplicates",
+            summary.imported, summary.duplicates
+        );
+    }
+
+    Ok(ImportPreview {
+        summary,
+        entries: added,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_entries, lock_test_env};
+    use chrono::TimeZone;
+
+    fn preset(name: &str) -> ColumnMapping {
+        get_import_presets().remove(name).unwrap()
+    }
+
+    fn entries(content: &str, mapping: &ColumnMapping) -> Vec<TimeSheetEntry> {
+        read_csv(content, mapping)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_clockify() {
+        let content = "\u{feff}Project,Client,Description,Task,User,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)\n\
+            Website,Acme,Mockups,Design,Ann,ann@example.com,\"design, web\",Yes,06/10/2025,11:30:00 PM,06/11/2025,01:00:00 AM,01:30:00,1.50\n";
+        let entries = entries(content, &preset("clockify"));
+
+        let start = Local.with_ymd_and_hms(2025, 6, 10, 23, 30, 0).unwrap();
+        assert_eq!(entries[0].start_time, start);
+        assert_eq!(entries[0].end_time, Some(start + Duration::minutes(90)));
+        assert_eq!(entries[0].tags, vec!["design", "web"]);
+        assert_eq!(entries[0].properties["project"], "Website");
+        assert_eq!(entries[0].properties["billable"], "true");
+        assert_eq!(entries[0].properties["client"], "Acme");
+    }
+
+    #[test]
+    fn test_reject_non_ascii_delimiter() {
+        let mapping = ColumnMapping {
+            delimiter: '¦',
+            ..Default::default()
+        };
+        assert!(read_csv("Description¦Start date\n", &mapping).is_err());
+    }
+
+    #[test]
+    fn test_harvest_lays_out_days() {
+        let content = "Date,Client,Project,Project Code,Task,Notes,Hours,Hours Rounded,Billable?\n\
+            2025-06-10,Acme,Website,WEB,Design,Mockups,1.5,1.5,Yes\n\
+            2025-06-10,Acme,Website,WEB,Meetings,,0.25,0.25,No\n\
+            2025-06-11,Acme,Website,WEB,Design,,2,2,Yes\n";
+        let entries = entries(content, &preset("harvest"));
+
+        let day = Local.with_ymd_and_hms(2025, 6, 10, 9, 0, 0).unwrap();
+        let spans: Vec<_> = entries
+            .iter()
+            .map(|e| (e.start_time, e.end_time.unwrap()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (day, day + Duration::minutes(90)),
+                (day + Duration::minutes(90), day + Duration::minutes(105)),
+                (
+                    day + Duration::days(1),
+                    day + Duration::days(1) + Duration::hours(2)
+                ),
+            ]
+        );
+        assert_eq!(entries[0].description, "Mockups");
+        assert_eq!(entries[1].description, "Meetings", "Falls back on the task");
+        assert_eq!(entries[1].properties["billable"], "false");
+        assert_eq!(entries[1].properties["project_code"], "WEB");
+    }
+
+    #[test]
+    fn test_custom_mapping_dry_run_and_import() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TOGGL_SHEET_PATH");
+
+        let path = dir.path().join("export.csv");
+        std::fs::write(
+            &path,
+            "Wann;Dauer;Was;Schlagworte\n\
+             10.06.2025 09:00;1:30;Mockups;design|web\n\
+             10.06.2025 11:00;0:15;Standup;\n\
+             kaputt;0:15;Broken;\n",
+        )
+        .unwrap();
+        let mapping = ColumnMapping {
+            delimiter: ';',
+            description: vec!["Was".to_string()],
+            start_date: "Wann".to_string(),
+            start_time: None,
+            duration: Some("Dauer".to_string()),
+            date_format: "%d.%m.%Y %H:%M".to_string(),
+            tags: Some("Schlagworte".to_string()),
+            tag_separator: "|".to_string(),
+            ..Default::default()
+        };
+        let path = path.to_string_lossy().to_string();
+
+        let preview = import_csv(&cache, path.clone(), mapping.clone(), true).unwrap();
+        assert_eq!(preview.summary.imported, 2);
+        assert_eq!(preview.entries[0].tags, vec!["design", "web"]);
+        assert_eq!(preview.errors.len(), 1);
+        assert!(preview.errors[0].starts_with("Row 3"));
+        assert!(get_entries().is_empty(), "Dry runs don't write");
+        assert!(import_csv(&cache, path.clone(), mapping.clone(), false).is_err());
+
+        std::fs::write(
+            &path,
+            "Wann;Dauer;Was;Schlagworte\n10.06.2025 09:00;1:30;Mockups;design|web\n",
+        )
+        .unwrap();
+        let report = import_csv(&cache, path.clone(), mapping.clone(), false).unwrap();
+        assert_eq!(report.summary.imported, 1);
+        assert_eq!(get_entries().len(), 1);
+        let report = import_csv(&cache, path, mapping, false).unwrap();
+        assert_eq!(report.summary.duplicates, 1);
+        assert_eq!(get_entries().len(), 1);
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use std::ops::{Deref, RangeBounds};
+
+use chrono::NaiveDate;
+
+use crate::TimeSheetEntry;
+
+/// Entries in file order, plus their positions grouped by local start date.
+#[derive(Debug, Default)]
+pub struct EntryIndex {
+    entries: Vec<TimeSheetEntry>,
+    by_date: BTreeMap<NaiveDate, Vec<usize>>,
+}
+
+impl EntryIndex {
+    pub fn new(entries: Vec<TimeSheetEntry>) -> Self {
+        let mut by_date = BTreeMap::<NaiveDate, Vec<usize>>::new();
+        for (i, entry) in entries.iter().enumerate() {
+            by_date.entry(entry.start_time.date_naive()).or_default().push(i);
+        }
+
+        Self { entries, by_date }
+    }
+
+    /// Entries that started on the given local date.
+    pub fn on_date(&self, date: NaiveDate) -> impl Iterator<Item = &TimeSheetEntry> {
+        self.by_date
+            .get(&date)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Entries that started within the range of local dates, ordered by date.
+    pub fn in_range(&self, dates: impl RangeBounds<NaiveDate>) -> impl Iterator<Item = &TimeSheetEntry> {
+        self.by_date
+            .range(dates)
+            .flat_map(|(_, indices)| indices.iter())
+            .map(|&i| &self.entries[i])
+    }
+}
+
+impl Deref for EntryIndex {
+    type Target = [TimeSheetEntry];
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl From<Vec<TimeSheetEntry>> for EntryIndex {
+    fn from(entries: Vec<TimeSheetEntry>) -> Self {
+        Self::new(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local, TimeZone};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_date_lookups() {
+        let start = Local.with_ymd_and_hms(2025, 5, 19, 9, 0, 0).unwrap();
+        // Out of order on purpose, like after editing an old entry
+        let entries: Vec<TimeSheetEntry> = [3, 0, 1, 0, 7]
+            .into_iter()
+            .map(|day| TimeSheetEntry {
+                description: format!("Day {day}"),
+                start_time: start + Duration::days(day),
+                end_time: None,
+                tags: vec![],
+                properties: HashMap::new(),
+            })
+            .collect();
+        let index = EntryIndex::new(entries);
+        let date = start.date_naive();
+
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.on_date(date).count(), 2);
+        assert_eq!(index.on_date(date + Duration::days(2)).count(), 0);
+
+        let week: Vec<&str> = index
+            .in_range(date..=date + Duration::days(6))
+            .map(|e| e.description.as_str())
+            .collect();
+        assert_eq!(week, vec!["Day 0", "Day 0", "Day 1", "Day 3"]);
+    }
+}
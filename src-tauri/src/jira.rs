@@ -1,12 +1,18 @@
-use std::collections::HashMap;
-use crate::{get_entries, update_entry, TimeSheetEntry, TimeSheetEntryFrontEnd};
+use crate::cache::EntryCache;
+use crate::rounding::{Rounding, RoundingScope};
+use crate::{get_entries, update_entry, TimeSheetEntry};
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use serde::Deserialize;
+use std::collections::HashMap;
 
-async fn create_worklog(entry: &TimeSheetEntry) -> Result<Worklog, ()> {
+async fn create_worklog(
+    cache: &EntryCache,
+    entry: &TimeSheetEntry,
+    time_spent: Duration,
+) -> Result<Worklog, ()> {
     if entry.properties.contains_key("jira_worklog_id") {
         return Err(());
     }
@@ -16,10 +22,7 @@ async fn create_worklog(entry: &TimeSheetEntry) -> Result<Worklog, ()> {
     };
 
     let started = format_for_jira(&entry.start_time.to_utc());
-    let time_spent_seconds = ((entry.end_time.unwrap().timestamp_millis()
-        - entry.start_time.timestamp_millis()) as f32)
-        / (1000.0);
-    let time_spent_seconds = time_spent_seconds as u32;
+    let time_spent_seconds = time_spent.num_seconds() as u32;
 
     let body = format!(
         r#"{{
@@ -38,17 +41,7 @@ async fn create_worklog(entry: &TimeSheetEntry) -> Result<Worklog, ()> {
             std::env::var("VITE_JIRA_URL_PREFIX").unwrap(),
             jira_id
         ))
-        .header(
-            AUTHORIZATION,
-            format!(
-                "Basic {}",
-                BASE64_STANDARD.encode(format!(
-                    "{}:{}",
-                    std::env::var("JIRA_USERNAME").unwrap(),
-                    std::env::var("JIRA_PASSWORD").unwrap()
-                ))
-            ),
-        )
+        .header(AUTHORIZATION, basic_auth())
         .header(CONTENT_TYPE, "application/json")
         .header("Accept", "application/json")
         .body(body)
@@ -73,17 +66,57 @@ async fn create_worklog(entry: &TimeSheetEntry) -> Result<Worklog, ()> {
     new_entry
         .properties
         .insert("jira_worklog_id".to_string(), worklog.id.clone());
-    let new_entry = TimeSheetEntryFrontEnd::from(new_entry);
-    let description = new_entry.description.clone();
-    let start_time = new_entry.start_time.clone();
-    if !update_entry(description, start_time, new_entry) {
+    new_entry.properties.insert(
+        "jira_time_spent".to_string(),
+        time_spent_seconds.to_string(),
+    );
+    if !update_entry(
+        cache,
+        entry.description.clone(),
+        entry.start_time,
+        new_entry,
+    ) {
         panic!("Failed to update entry");
     }
 
     Ok(worklog)
 }
 
-pub async fn add_missing_worklogs() {
+pub async fn delete_worklog(jira_id: &str, worklog_id: &str) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let response = client
+        .delete(format!(
+            "{}rest/api/2/issue/{}/worklog/{}",
+            std::env::var("VITE_JIRA_URL_PREFIX").unwrap(),
+            jira_id,
+            worklog_id
+        ))
+        .header(AUTHORIZATION, basic_auth())
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| println!("Error deleting worklog: {e}"))?;
+
+    if !response.status().is_success() {
+        println!("Error deleting worklog: {}", response.status());
+        return Err(());
+    }
+
+    Ok(())
+}
+
+fn basic_auth() -> String {
+    format!(
+        "Basic {}",
+        BASE64_STANDARD.encode(format!(
+            "{}:{}",
+            std::env::var("JIRA_USERNAME").unwrap(),
+            std::env::var("JIRA_PASSWORD").unwrap()
+        ))
+    )
+}
+
+pub async fn add_missing_worklogs(cache: &EntryCache) {
     let jira_prefix_url = std::env::var("VITE_JIRA_URL_PREFIX").unwrap();
 
     for (jira_id, entries) in get_jira_entries() {
@@ -92,17 +125,86 @@ pub async fn add_missing_worklogs() {
             continue;
         }
         println!("{jira_prefix_url}browse/{jira_id}");
-        for entry in entries.iter() {
-            if entry.properties.contains_key("jira_worklog_id") {
+        for (entry, time_spent) in time_to_log(&entries, Rounding::from_env()) {
+            println!("{}", entry.description);
+            if time_spent.num_seconds() <= 0 {
+                println!("Skipped, rounded down to nothing");
                 continue;
             }
-            println!("{}", entry.description);
-            let _r = create_worklog(entry).await.unwrap();
+            let _r = create_worklog(cache, entry, time_spent).await.unwrap();
             // println!("{r:#?}");
         }
     }
 }
 
+/// Seconds logged by the entry's worklog, `None` when it has none yet.
+fn logged_time(entry: &TimeSheetEntry, rounding: Option<Rounding>) -> Option<Duration> {
+    entry.properties.get("jira_worklog_id")?;
+    match entry.properties.get("jira_time_spent") {
+        Some(seconds) => Some(Duration::seconds(seconds.parse().unwrap())),
+        // Logged before the time spent was kept
+        None => {
+            let exact = Duration::milliseconds(entry.duration_millis());
+            Some(rounding.map_or(exact, |r| r.round(exact)))
+        }
+    }
+}
+
+/// The issue's finished entries without a worklog yet, with the time to log for each.
+///
+/// With the day scope, what the day's worklogs already logged is taken out of its rounded total,
+/// so an increment rounded up once isn't logged again when entries get added to the day.
+fn time_to_log(
+    entries: &[TimeSheetEntry],
+    rounding: Option<Rounding>,
+) -> Vec<(&TimeSheetEntry, Duration)> {
+    let exact = |e: &TimeSheetEntry| Duration::milliseconds(e.duration_millis());
+    let logged: Vec<Option<Duration>> = entries.iter().map(|e| logged_time(e, rounding)).collect();
+    let mut to_log: Vec<Option<Duration>> = entries
+        .iter()
+        .zip(logged.iter())
+        .map(|(e, logged)| match logged {
+            // A running entry is logged once stopped
+            None if e.end_time.is_some() => Some(rounding.map_or(exact(e), |r| r.round(exact(e)))),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(rounding) = rounding.filter(|r| r.scope == RoundingScope::Day) {
+        let mut days = HashMap::<_, Vec<usize>>::new();
+        for (i, entry) in entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.end_time.is_some())
+        {
+            days.entry(entry.start_time.date_naive())
+                .or_default()
+                .push(i);
+        }
+        for indices in days.into_values() {
+            let total = rounding.round(indices.iter().map(|&i| exact(&entries[i])).sum());
+            let already_logged: Duration = indices.iter().filter_map(|&i| logged[i]).sum();
+            let new: Vec<usize> = indices
+                .into_iter()
+                .filter(|&i| logged[i].is_none())
+                .collect();
+            let durations = rounding.split(
+                (total - already_logged).max(Duration::zero()),
+                &new.iter().map(|&i| exact(&entries[i])).collect::<Vec<_>>(),
+            );
+            for (i, duration) in new.into_iter().zip(durations) {
+                to_log[i] = Some(duration);
+            }
+        }
+    }
+
+    entries
+        .iter()
+        .zip(to_log)
+        .filter_map(|(e, duration)| Some((e, duration?)))
+        .collect()
+}
+
 fn get_jira_entries() -> HashMap<String, Vec<TimeSheetEntry>> {
     let entries = get_entries();
 
@@ -157,6 +259,51 @@ mod tests {
     use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
     use std::collections::HashMap;
 
+    #[test]
+    fn test_time_to_log_after_earlier_worklogs() {
+        let entry = |hour, minutes| {
+            let start_time = Local.with_ymd_and_hms(2025, 6, 10, hour, 0, 0).unwrap();
+            TimeSheetEntry {
+                description: "Work".to_string(),
+                start_time,
+                end_time: Some(start_time + Duration::minutes(minutes)),
+                tags: vec![],
+                properties: HashMap::from([("jira".to_string(), "ABC-1".to_string())]),
+            }
+        };
+        let rounding = Some(Rounding {
+            mode: crate::rounding::RoundingMode::Nearest,
+            minutes: 15,
+            scope: RoundingScope::Day,
+        });
+        let minutes = |to_log: Vec<(&TimeSheetEntry, Duration)>| -> Vec<i64> {
+            to_log.iter().map(|(_, d)| d.num_minutes()).collect()
+        };
+
+        // First sync with 10 minutes, logged as 15
+        let mut entries = vec![entry(9, 10)];
+        assert_eq!(minutes(time_to_log(&entries, rounding)), [15]);
+        for (key, value) in [("jira_worklog_id", "10001"), ("jira_time_spent", "900")] {
+            entries[0]
+                .properties
+                .insert(key.to_string(), value.to_string());
+        }
+
+        // 22 minutes for the day still round to 15, already logged
+        entries.push(entry(10, 12));
+        assert_eq!(minutes(time_to_log(&entries, rounding)), [0]);
+        // 38 minutes round to 45, the 30 more than logged split over the entries not logged yet
+        entries.push(entry(11, 16));
+        assert_eq!(minutes(time_to_log(&entries, rounding)), [15, 15]);
+
+        // A running entry neither gets a worklog nor counts towards the day yet
+        let mut running = entry(12, 0);
+        running.end_time = None;
+        entries.push(running);
+        assert_eq!(minutes(time_to_log(&entries, rounding)), [15, 15]);
+        assert_eq!(time_to_log(&entries, None).len(), 2);
+    }
+
     #[test]
     fn test_total_jira_time() {
         dotenvy::dotenv().unwrap();
@@ -282,7 +429,10 @@ mod tests {
             .unwrap();
         println!("{entry:#?}");
 
-        let r = create_worklog(entry).await.unwrap();
+        let (_, time_spent) = crate::rounding::rounded_durations([entry])[0];
+        let r = create_worklog(&EntryCache::new(), entry, time_spent)
+            .await
+            .unwrap();
         println!("{r:#?}");
     }
 
@@ -292,7 +442,7 @@ mod tests {
 
         //op run --env-file ../.env -- cargo test test_add_missing_worklogs -- --nocapture
 
-        add_missing_worklogs().await;
+        add_missing_worklogs(&EntryCache::new()).await;
     }
 
     #[test]
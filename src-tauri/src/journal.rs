@@ -0,0 +1,316 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::{jira, timesheet_sidecar_path, trash, write_entries, TimeSheetEntry};
+
+/// How many operations are kept for undo, older ones are dropped.
+const MAX_JOURNAL_LENGTH: usize = 200;
+
+/// Reversible change made to the timesheet through `add_entry`, `update_entry` or `delete_entry`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+    Add { entry: TimeSheetEntry },
+    Update { old: TimeSheetEntry, new: TimeSheetEntry },
+    Delete { entry: TimeSheetEntry },
+    /// Operations made by one user action, undone together
+    Batch { operations: Vec<Operation> },
+}
+
+impl Operation {
+    pub fn inverse(&self) -> Operation {
+        match self.clone() {
+            Operation::Add { entry } => Operation::Delete { entry },
+            Operation::Update { old, new } => Operation::Update { old: new, new: old },
+            Operation::Delete { entry } => Operation::Add { entry },
+            Operation::Batch { operations } => Operation::Batch {
+                operations: operations.iter().rev().map(Operation::inverse).collect(),
+            },
+        }
+    }
+
+    pub fn apply(&self, entries: &mut Vec<TimeSheetEntry>) -> Result<(), String> {
+        match self {
+            Operation::Add { entry } => entries.push(entry.clone()),
+            Operation::Update { old, new } => {
+                let index = find_entry(entries, old)?;
+                entries[index] = new.clone();
+            }
+            Operation::Delete { entry } => {
+                let index = find_entry(entries, entry)?;
+                entries.remove(index);
+            }
+            Operation::Batch { operations } => {
+                for operation in operations {
+                    operation.apply(entries)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Jira keys and worklog ids that applying this operation would drop from the timesheet.
+    ///
+    /// Once the worklog id is gone, nothing links the worklog in Jira to the timesheet anymore.
+    fn dropped_worklogs(&self) -> Vec<(String, String)> {
+        let (removed, kept) = match self {
+            Operation::Add { .. } => return Vec::new(),
+            Operation::Update { old, new } => (old, Some(new)),
+            Operation::Delete { entry } => (entry, None),
+            Operation::Batch { operations } => {
+                return operations.iter().flat_map(Operation::dropped_worklogs).collect();
+            }
+        };
+
+        let Some(worklog_id) = removed.properties.get("jira_worklog_id") else {
+            return Vec::new();
+        };
+        if kept.and_then(|k| k.properties.get("jira_worklog_id")) == Some(worklog_id) {
+            return Vec::new();
+        }
+        let Some(jira_id) = removed.properties.get("jira") else {
+            return Vec::new();
+        };
+
+        vec![(jira_id.clone(), worklog_id.clone())]
+    }
+
+    /// Takes added entries out of the trash and puts deleted ones in it.
+    fn update_trash(&self) {
+        match self {
+            Operation::Add { entry } => trash::forget(entry),
+            Operation::Delete { entry } => trash::put(vec![entry.clone()]),
+            Operation::Update { .. } => {}
+            Operation::Batch { operations } => operations.iter().for_each(Operation::update_trash),
+        }
+    }
+}
+
+fn find_entry(entries: &[TimeSheetEntry], entry: &TimeSheetEntry) -> Result<usize, String> {
+    entries
+        .iter()
+        .position(|e| e.description == entry.description && e.start_time == entry.start_time)
+        .ok_or_else(|| format!("Entry \"{}\" not found in timesheet", entry.description))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    done: Vec<Operation>,
+    undone: Vec<Operation>,
+}
+
+/// `JOURNAL_PATH`, or `<timesheet>.journal.json` next to `TIMESHEET_PATH`.
+fn journal_path() -> PathBuf {
+    match std::env::var("JOURNAL_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("journal.json"),
+    }
+}
+
+fn load_journal() -> Journal {
+    let path = journal_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Journal::default();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Journal file should be valid json")
+}
+
+fn write_journal(journal: &Journal) {
+    let file = std::fs::File::create(journal_path()).unwrap();
+    serde_json::to_writer(file, journal).unwrap();
+}
+
+/// Records the operations of one user action as a single undo step, nothing if there are none.
+pub fn record_all(mut operations: Vec<Operation>) {
+    match operations.len() {
+        0 => {}
+        1 => record(operations.remove(0)),
+        _ => record(Operation::Batch { operations }),
+    }
+}
+
+/// Records an operation that was just applied to the timesheet, which clears the redo history.
+pub fn record(operation: Operation) {
+    let mut journal = load_journal();
+    journal.done.push(operation);
+    if journal.done.len() > MAX_JOURNAL_LENGTH {
+        journal.done.drain(..journal.done.len() - MAX_JOURNAL_LENGTH);
+    }
+    journal.undone.clear();
+    write_journal(&journal);
+}
+
+/// Applies the operation, refusing if it would orphan a Jira worklog unless `revert_worklog` deletes it first.
+async fn apply_checked(
+    cache: &EntryCache,
+    operation: &Operation,
+    revert_worklog: bool,
+) -> Result<(), String> {
+    let mut entries = cache.entries().to_vec();
+    operation.apply(&mut entries)?;
+
+    let dropped = operation.dropped_worklogs();
+    if let Some((jira_id, worklog_id)) = dropped.first() {
+        if !revert_worklog {
+            return Err(format!(
+                "Entry was already synced to {jira_id} as worklog {worklog_id}, revert the worklog too to undo it"
+            ));
+        }
+    }
+    for (jira_id, worklog_id) in dropped {
+        jira::delete_worklog(&jira_id, &worklog_id)
+            .await
+            .map_err(|_| format!("Failed to delete worklog {worklog_id} on {jira_id}"))?;
+        println!("[INFO] Deleted worklog {worklog_id} on {jira_id}");
+    }
+
+    write_entries(cache, &entries);
+    operation.update_trash();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn undo(cache: &EntryCache, revert_worklog: bool) -> Result<(), String> {
+    let mut journal = load_journal();
+    let Some(operation) = journal.done.pop() else {
+        return Err("Nothing to undo".to_string());
+    };
+
+    apply_checked(cache, &operation.inverse(), revert_worklog).await?;
+
+    journal.undone.push(operation);
+    write_journal(&journal);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn redo(cache: &EntryCache, revert_worklog: bool) -> Result<(), String> {
+    let mut journal = load_journal();
+    let Some(operation) = journal.undone.pop() else {
+        return Err("Nothing to redo".to_string());
+    };
+
+    apply_checked(cache, &operation, revert_worklog).await?;
+
+    journal.done.push(operation);
+    write_journal(&journal);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_entry, delete_entry, get_entries, lock_test_env, update_entry};
+    use chrono::{DateTime, Duration, Local, SubsecRound};
+
+    fn new_entry(description: &str, start_time: DateTime<Local>) -> TimeSheetEntry {
+        TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(5)),
+            tags: vec!["test".to_string()],
+            properties: Default::default(),
+        }
+    }
+
+    fn descriptions() -> Vec<String> {
+        get_entries().into_iter().map(|e| e.description).collect()
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("JOURNAL_PATH");
+
+        // Stored with millisecond precision
+        let now = Local::now().trunc_subsecs(3);
+        assert!(add_entry(&cache, new_entry("First", now)));
+        assert!(add_entry(&cache, new_entry("Second", now + Duration::seconds(1))));
+        let second = now + Duration::seconds(1);
+        assert!(update_entry(&cache, "Second".to_string(), second, new_entry("Renamed", second)));
+        assert!(delete_entry(&cache, "First".to_string(), now));
+        assert_eq!(descriptions(), vec!["Renamed"]);
+
+        block_on(undo(&cache, false)).unwrap();
+        assert_eq!(descriptions(), vec!["Renamed", "First"]);
+        block_on(undo(&cache, false)).unwrap();
+        assert_eq!(descriptions(), vec!["Second", "First"]);
+
+        block_on(redo(&cache, false)).unwrap();
+        assert_eq!(descriptions(), vec!["Renamed", "First"]);
+
+        // A new change drops what was left to redo
+        assert!(add_entry(&cache, new_entry("Third", now + Duration::seconds(2))));
+        assert!(block_on(redo(&cache, false)).is_err());
+
+        for _ in 0..4 {
+            block_on(undo(&cache, false)).unwrap();
+        }
+        assert!(descriptions().is_empty());
+        assert!(block_on(undo(&cache, false)).is_err());
+    }
+
+    #[test]
+    fn test_undo_deleting_duplicates() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("JOURNAL_PATH");
+
+        // Stored with millisecond precision
+        let now = Local::now().trunc_subsecs(3);
+        assert!(add_entry(&cache, new_entry("Twice", now)));
+        assert!(add_entry(&cache, new_entry("Twice", now)));
+        assert!(delete_entry(&cache, "Twice".to_string(), now));
+        assert!(descriptions().is_empty());
+
+        block_on(undo(&cache, false)).unwrap();
+        assert_eq!(descriptions(), vec!["Twice", "Twice"]);
+        block_on(redo(&cache, false)).unwrap();
+        assert!(descriptions().is_empty());
+    }
+
+    #[test]
+    fn test_refuse_undoing_synced_entry() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("JOURNAL_PATH");
+
+        // Stored with millisecond precision
+        let now = Local::now().trunc_subsecs(3);
+        let mut entry = new_entry("Synced", now);
+        entry.properties.insert("jira".to_string(), "ABC-1".to_string());
+        assert!(add_entry(&cache, entry.clone()));
+        entry.properties.insert("jira_worklog_id".to_string(), "1234".to_string());
+        assert!(update_entry(&cache, "Synced".to_string(), now, entry));
+
+        let error = block_on(undo(&cache, false)).unwrap_err();
+        assert!(error.contains("1234"), "{error}");
+        assert_eq!(
+            get_entries()[0].properties.get("jira_worklog_id").map(String::as_str),
+            Some("1234")
+        );
+    }
+}
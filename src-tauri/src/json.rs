@@ -0,0 +1,139 @@
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::cache::EntryCache;
+use crate::import::{self, ImportSummary};
+use crate::{write_entries, TimeSheetEntry};
+
+/// `.jsonl` and `.ndjson` files hold one entry per line, others a single array.
+fn is_json_lines(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "jsonl" || ext == "ndjson")
+}
+
+/// Writes the entries in their json form, running ones included, returning how many were written.
+pub fn write_json<'a>(
+    path: impl AsRef<Path>,
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+) -> usize {
+    let path = path.as_ref();
+    let mut writer = BufWriter::new(std::fs::File::create(path).unwrap());
+    let entries: Vec<&TimeSheetEntry> = entries.into_iter().collect();
+    if is_json_lines(path) {
+        for entry in entries.iter() {
+            serde_json::to_writer(&mut writer, entry).unwrap();
+            writer.write_all(b"\n").unwrap();
+        }
+    } else {
+        serde_json::to_writer_pretty(&mut writer, &entries).unwrap();
+    }
+    writer.flush().unwrap();
+
+    entries.len()
+}
+
+/// Reads entries written by [`write_json`], or by hand in the same form.
+pub fn read_json(path: impl AsRef<Path>) -> Result<Vec<TimeSheetEntry>, String> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    if !is_json_lines(path) {
+        return serde_json::from_reader(BufReader::new(file)).map_err(|e| e.to_string());
+    }
+
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(i, line)| {
+            let line = line.map_err(|e| e.to_string())?;
+            serde_json::from_str(&line).map_err(|e| format!("Line {}: {e}", i + 1))
+        })
+        .collect()
+}
+
+/// Exports the whole timesheet, as json lines when the path ends with `.jsonl`.
+#[tauri::command]
+pub fn export_json(cache: &EntryCache, path: String) -> usize {
+    let entries = cache.entries();
+    let count = write_json(&path, entries.iter());
+    println!("[INFO] Exported {count} entries to {path}");
+
+    count
+}
+
+/// Adds the entries of a json export missing from the timesheet.
+#[tauri::command]
+pub fn import_json(cache: &EntryCache, path: String) -> Result<ImportSummary, String> {
+    let imported = read_json(&path)?;
+    let mut entries = cache.entries().to_vec();
+    let report = import::merge(&mut entries, imported);
+    if report.imported > 0 || report.enriched > 0 {
+        write_entries(cache, &entries);
+    }
+
+    println!(
+        "[INFO] Imported {} entries from {path}, skipped {} duplicates",
+        report.imported, report.duplicates
+    );
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_entries, lock_test_env};
+    use chrono::{Duration, Local, TimeZone};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_round_trip_through_csv() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::remove_var("TOGGL_SHEET_PATH");
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+
+        let start =
+            Local.with_ymd_and_hms(2025, 6, 10, 9, 0, 0).unwrap() + Duration::milliseconds(250);
+        write_entries(
+            &cache,
+            &[
+                TimeSheetEntry {
+                    description: "Review \"fixes\"\nand notes".to_string(),
+                    start_time: start,
+                    end_time: Some(start + Duration::minutes(90)),
+                    tags: vec!["dev".to_string(), "PROJ".to_string()],
+                    properties: HashMap::from([
+                        ("jira".to_string(), "PROJ-1".to_string()),
+                        ("jira_worklog_id".to_string(), "10001".to_string()),
+                    ]),
+                },
+                TimeSheetEntry {
+                    description: "Running".to_string(),
+                    start_time: start + Duration::hours(2),
+                    end_time: None,
+                    tags: vec!["meeting".to_string()],
+                    properties: HashMap::new(),
+                },
+            ],
+        );
+        let stored = get_entries();
+
+        for name in ["export.json", "export.jsonl"] {
+            let path = dir.path().join(name);
+            assert_eq!(export_json(&cache, path.to_string_lossy().to_string()), 2);
+            assert_eq!(read_json(&path).unwrap(), stored, "{name}");
+
+            std::env::set_var("TIMESHEET_PATH", dir.path().join(format!("{name}.csv")));
+            let report = import_json(&cache, path.to_string_lossy().to_string()).unwrap();
+            assert_eq!(report.imported, 2);
+            assert_eq!(get_entries(), stored, "{name}");
+            std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        }
+
+        let lines = std::fs::read_to_string(dir.path().join("export.jsonl")).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+        assert!(lines.contains(r#""tags":["dev","PROJ"]"#));
+    }
+}
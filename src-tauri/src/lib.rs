@@ -1,75 +1,158 @@
+pub mod backup;
+pub mod billing;
+pub mod budgets;
+pub mod cache;
+pub mod conflicts;
+pub mod drafts;
+pub mod eventlog;
+pub mod excel;
+pub mod format;
+pub mod ics;
+pub mod idle;
+pub mod import;
+pub mod index;
 pub mod jira;
+pub mod journal;
+pub mod json;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod reminders;
+pub mod report;
+pub mod rounding;
+pub mod suggest;
+pub mod templates;
+pub mod toggl;
+pub mod trash;
 
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
-use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, Weekday};
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
+use tauri::Manager;
+
+use crate::cache::EntryCache;
 
 pub fn get_entries() -> Vec<TimeSheetEntry> {
     let mut entries: Vec<TimeSheetEntry> = Vec::new();
     if let Ok(toggl_sheet_path) = std::env::var("TOGGL_SHEET_PATH") {
-        let mut toggl_rdr = csv::ReaderBuilder::new()
-            .delimiter(b',')
-            .from_path(toggl_sheet_path)
-            .unwrap();
-        entries.extend(
-            toggl_rdr
-                .deserialize::<TogglEntryRaw>()
-                .into_iter()
-                .map(|e| {
-                    let mut entry: TimeSheetEntry = e.unwrap().try_into().unwrap();
-                    if !entry.tags.iter().any(|t| t == "Toggl") {
-                        entry.tags.push("Toggl".to_string());
-                    }
-                    entry
-                }),
-        );
+        entries.extend(toggl::read_toggl_sheet(toggl_sheet_path).unwrap());
     }
 
-    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
-    if std::fs::exists(&timesheet_path).unwrap() {
-        let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(b',')
-            .from_path(timesheet_path)
-            .unwrap();
-        entries.extend(
-            rdr.deserialize::<TimeSheetEntryRaw>()
-                .into_iter()
-                .map(|e| e.unwrap().try_into().unwrap()),
-        );
+    match StorageMode::from_env() {
+        StorageMode::Csv => {
+            let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
+            format::upgrade_if_needed();
+            if std::fs::exists(&timesheet_path).unwrap() {
+                entries.extend(read_timesheet(timesheet_path));
+            }
+        }
+        StorageMode::EventLog => entries.extend(eventlog::load()),
+        #[cfg(feature = "sqlite")]
+        StorageMode::Sqlite => entries.extend(sqlite::load()),
     }
 
     entries
 }
 
+/// Where entries are stored, from `STORAGE_MODE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageMode {
+    /// `csv`, the default: the whole timesheet is rewritten at `TIMESHEET_PATH` on every change.
+    Csv,
+    /// `eventlog`: changes are appended to per-machine logs, see [`eventlog`].
+    EventLog,
+    /// `sqlite`: entries are stored in a database, see [`sqlite`]. Needs the `sqlite` feature.
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl StorageMode {
+    pub fn from_env() -> Self {
+        match std::env::var("STORAGE_MODE").as_deref() {
+            Err(_) | Ok("csv") => StorageMode::Csv,
+            Ok("eventlog") => StorageMode::EventLog,
+            #[cfg(feature = "sqlite")]
+            Ok("sqlite") => StorageMode::Sqlite,
+            #[cfg(not(feature = "sqlite"))]
+            Ok("sqlite") => panic!("STORAGE_MODE sqlite needs building with the sqlite feature"),
+            Ok(mode) => panic!("Unknown STORAGE_MODE {mode}, expected csv, eventlog or sqlite"),
+        }
+    }
+}
+
+/// Reads entries from a file in the timesheet's own csv format, whatever its [`format`] version.
+pub fn read_timesheet(path: impl AsRef<std::path::Path>) -> Vec<TimeSheetEntry> {
+    let table = format::read_table(path);
+    table
+        .records
+        .iter()
+        .map(|record| {
+            record
+                .deserialize::<TimeSheetEntryRaw>(Some(&table.headers))
+                .unwrap()
+                .try_into()
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Writes entries to a file in the timesheet's own csv format, starting with its [`format`] version.
+pub fn write_timesheet(path: impl AsRef<std::path::Path>, entries: &[TimeSheetEntry]) {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(path).unwrap();
+    file.write_all(format::version_line().as_bytes()).unwrap();
+    // Quotes descriptions starting with '#', so they can't be mistaken for comments
+    let mut writer = csv::WriterBuilder::new()
+        .comment(Some(b'#'))
+        .from_writer(file);
+    for entry in entries {
+        writer.serialize(TimeSheetEntryRaw::from(entry)).unwrap();
+    }
+    writer.flush().unwrap();
+}
+
+/// Parses dates in the `YYYY-MM-DD` form the frontend and CLI use.
+pub(crate) fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("Invalid date {date}: {e}"))
+}
+
+/// Hours to work on the day, `DAILY_TARGET_HOURS` or 8 on weekdays and none on weekends.
+pub(crate) fn daily_target_hours(date: NaiveDate) -> f64 {
+    if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+        return 0.0;
+    }
+    std::env::var("DAILY_TARGET_HOURS")
+        .map(|hours| {
+            hours
+                .parse()
+                .expect("DAILY_TARGET_HOURS should be a number")
+        })
+        .unwrap_or(8.0)
+}
+
 //TODO unwrap to ?
 #[tauri::command]
-fn get_date_entries(date: &str) -> Vec<TimeSheetEntryFrontEnd> {
-    let entries: Vec<TimeSheetEntry> = get_entries();
-
+fn get_date_entries(cache: &EntryCache, date: &str) -> Vec<TimeSheetEntry> {
     let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
 
-    entries
-        .into_iter()
-        // .map(|entry| entry.unwrap())
-        .filter(|e| e.start_time.date_naive() == date)
-        .map(|e| e.into())
-        .collect::<Vec<TimeSheetEntryFrontEnd>>()
+    cache.in_range(date..=date)
 }
 
 #[tauri::command]
-fn add_entry(entry: TimeSheetEntryFrontEnd) -> bool {
-    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
-    let existing_entries = get_entries();
-    let mut writer = csv::Writer::from_path(&timesheet_path).unwrap();
-
-    for existing_entry in existing_entries {
-        writer.serialize(existing_entry).unwrap();
+fn add_entry(cache: &EntryCache, entry: TimeSheetEntry) -> bool {
+    match StorageMode::from_env() {
+        #[cfg(feature = "sqlite")]
+        StorageMode::Sqlite => sqlite::insert_entry(cache, &entry),
+        _ => {
+            let mut entries = cache.entries().to_vec();
+            entries.push(entry.clone());
+            write_entries(cache, &entries);
+        }
     }
-    let entry: TimeSheetEntry = entry.try_into().unwrap();
-    writer.serialize(entry).unwrap();
-    writer.flush().unwrap();
+    journal::record(journal::Operation::Add { entry });
 
     println!("[INFO] Entry added to timesheet");
 
@@ -78,99 +161,117 @@ fn add_entry(entry: TimeSheetEntryFrontEnd) -> bool {
 
 #[tauri::command]
 fn update_entry(
+    cache: &EntryCache,
     old_description: String,
-    old_start_time: i64,
-    entry: TimeSheetEntryFrontEnd,
+    old_start_time: DateTime<Local>,
+    mut entry: TimeSheetEntry,
 ) -> bool {
-    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
-    let mut existing_entries = get_entries();
+    templates::apply_default_duration(&mut entry);
+
+    #[cfg(feature = "sqlite")]
+    if StorageMode::from_env() == StorageMode::Sqlite {
+        let old = sqlite::update_entry(cache, &old_description, old_start_time, &entry).expect("Entry not found");
+        journal::record(journal::Operation::Update { old, new: entry });
+        return true;
+    }
 
-    let entry: TimeSheetEntry = entry.try_into().unwrap();
-    let old_start_time = DateTime::from_timestamp_millis(old_start_time).unwrap();
+    let mut existing_entries = cache.entries().to_vec();
     let index = existing_entries
         .iter()
         .position(|e| old_description == e.description && old_start_time == e.start_time)
         .expect("Entry not found");
-    existing_entries[index] = entry;
-
-    let mut writer = csv::Writer::from_path(&timesheet_path).unwrap();
+    let old = std::mem::replace(&mut existing_entries[index], entry.clone());
 
-    for entry in existing_entries {
-        // let entry: TimeSheetEntry = raw_entry.try_into().unwrap();
-        writer.serialize(entry).unwrap();
-    }
-    writer.flush().unwrap();
+    write_entries(cache, &existing_entries);
+    journal::record(journal::Operation::Update { old, new: entry });
 
     true
 }
 
 #[tauri::command]
-fn delete_entry(description: String, start_time: i64) -> bool {
-    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
-    let existing_entries = match csv::Reader::from_path(&timesheet_path) {
-        Ok(mut reader) => reader
-            .deserialize::<TimeSheetEntryRaw>()
-            .into_iter()
-            .map(|e| e.unwrap().try_into().unwrap())
-            .collect::<Vec<TimeSheetEntry>>(),
-        //TODO Pattern match for NotFound specifically and panic otherwise
-        Err(_) => vec![],
-    };
-
-    let start_time = DateTime::from_timestamp_millis(start_time).unwrap();
-    let existing_entries: Vec<TimeSheetEntry> = existing_entries
+fn delete_entry(cache: &EntryCache, description: String, start_time: DateTime<Local>) -> bool {
+    #[cfg(feature = "sqlite")]
+    if StorageMode::from_env() == StorageMode::Sqlite {
+        let deleted = sqlite::delete_entries(cache, &description, start_time);
+        journal::record_all(deleted.iter().map(|entry| journal::Operation::Delete { entry: entry.clone() }).collect());
+        trash::put(deleted);
+        return true;
+    }
+
+    let existing_entries = cache.entries().to_vec();
+    let (deleted, existing_entries): (Vec<TimeSheetEntry>, Vec<TimeSheetEntry>) = existing_entries
         .into_iter()
-        .filter(|e| e.description != description || e.start_time != start_time)
-        .collect();
+        .partition(|e| e.description == description && e.start_time == start_time);
+
+    write_entries(cache, &existing_entries);
+    // Duplicates go away together, so undoing brings them all back
+    journal::record_all(deleted.iter().map(|entry| journal::Operation::Delete { entry: entry.clone() }).collect());
+    trash::put(deleted);
 
-    let mut writer = csv::Writer::from_path(&timesheet_path).unwrap();
+    true
+}
 
-    for entry in existing_entries {
-        writer.serialize(entry).unwrap();
+/// Rewrites the whole timesheet at `TIMESHEET_PATH` with the given entries,
+/// records the changes to get there when using the event log, or replaces the database's entries.
+pub fn write_entries(cache: &EntryCache, entries: &[TimeSheetEntry]) {
+    match StorageMode::from_env() {
+        StorageMode::Csv => {
+            backup::snapshot();
+            write_timesheet(std::env::var("TIMESHEET_PATH").unwrap(), entries);
+        }
+        StorageMode::EventLog => eventlog::save(entries),
+        #[cfg(feature = "sqlite")]
+        StorageMode::Sqlite => sqlite::save(entries),
     }
-    writer.flush().unwrap();
 
-    true
+    cache.invalidate();
+}
+
+/// Path of a file living next to the timesheet, e.g. `timesheet.templates.json` for `"templates.json"`.
+pub(crate) fn timesheet_sidecar_path(suffix: &str) -> PathBuf {
+    let timesheet_path = PathBuf::from(std::env::var("TIMESHEET_PATH").unwrap());
+    let stem = timesheet_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "timesheet".to_string());
+    timesheet_path.with_file_name(format!("{stem}.{suffix}"))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeSheetEntryTemplate {
-    description: String,
-    tags: Vec<String>,
-    properties: HashMap<String, String>,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub properties: HashMap<String, String>,
 }
 
-#[tauri::command]
-fn suggest_entry_descriptions(partial: &str) -> Vec<TimeSheetEntryTemplate> {
-    let entries: Vec<TimeSheetEntry> = get_entries();
-
-    let partial_lower = partial.to_lowercase();
-    let mut seen = HashSet::new();
-    let mut suggestions = Vec::new();
-    for entry in entries.iter().rev() {
-        let desc = entry.description.trim().to_lowercase();
-        let tags: std::collections::BTreeSet<_> =
-            entry.tags.iter().map(|s| s.trim().to_lowercase()).collect();
-        if desc.starts_with(&partial_lower) || desc.contains(&partial_lower) {
-            let key = (desc.clone(), tags.clone());
-            if !seen.contains(&key) {
-                seen.insert(key);
-                let mut properties = entry.properties.clone();
-                //TODO Distinguish between template properties and instance properties
-                properties.remove("jira_worklog_id");
-                //TODO TimeSheetEntry::into::<TimeSheetEntryTemplate>()
-                suggestions.push(TimeSheetEntryTemplate {
-                    description: entry.description.clone(),
-                    tags: entry.tags.clone(),
-                    properties,
-                });
-                if suggestions.len() >= 5 {
-                    break;
-                }
-            }
+impl From<&TimeSheetEntry> for TimeSheetEntryTemplate {
+    fn from(entry: &TimeSheetEntry) -> Self {
+        let mut properties = entry.properties.clone();
+        //TODO Distinguish between template properties and instance properties
+        properties.remove("jira_worklog_id");
+        properties.remove("jira_time_spent");
+        Self {
+            description: entry.description.clone(),
+            tags: entry.tags.clone(),
+            properties,
         }
     }
-    suggestions
+}
+
+#[tauri::command]
+fn suggest_entry_descriptions(
+    cache: &EntryCache,
+    partial: &str,
+    limit: Option<usize>,
+) -> Vec<TimeSheetEntryTemplate> {
+    let entries = cache.entries();
+
+    suggest::rank_suggestions(
+        &entries,
+        partial,
+        Local::now(),
+        limit.unwrap_or(suggest::DEFAULT_SUGGESTION_LIMIT),
+    )
 }
 
 fn _equivalent_entry(a: &TimeSheetEntry, b: &TimeSheetEntry) -> bool {
@@ -198,6 +299,19 @@ pub fn run() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
+        .manage(EntryCache::new())
+        .setup(|app| {
+            trash::purge_trash();
+            if StorageMode::from_env() == StorageMode::EventLog {
+                eventlog::compact_if_needed();
+            }
+            let watcher = cache::watch_timesheets(app.handle().clone())?;
+            app.manage(watcher);
+            reminders::start(app.handle().clone());
+            idle::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_date_entries,
             add_entry,
@@ -205,13 +319,55 @@ pub fn run() {
             delete_entry,
             suggest_entry_descriptions,
             get_remaining_week_hours,
+            templates::get_templates,
+            templates::save_template,
+            templates::delete_template,
+            templates::move_template,
+            templates::start_from_template,
+            journal::undo,
+            journal::redo,
+            trash::get_trash,
+            trash::restore_entry,
+            trash::purge_trash,
+            backup::list_backups,
+            backup::diff_backup,
+            backup::restore_backup,
+            eventlog::compact_event_log,
+            conflicts::find_conflict_copies,
+            conflicts::merge_conflict_copies,
+            toggl::export_toggl,
+            toggl::import_toggl,
+            import::get_import_presets,
+            import::import_csv,
+            ics::export_ics,
+            drafts::get_event_rules,
+            drafts::save_event_rules,
+            drafts::import_ics,
+            drafts::get_drafts,
+            drafts::update_draft,
+            drafts::discard_drafts,
+            drafts::accept_drafts,
+            json::export_json,
+            json::import_json,
+            excel::export_excel,
+            report::get_report,
+            report::export_report,
+            billing::get_rates,
+            billing::save_rates,
+            billing::get_billing_summary,
+            billing::export_billing,
+            budgets::get_budgets,
+            budgets::save_budgets,
+            budgets::get_budget_status,
+            idle::get_idle_period,
+            idle::resolve_idle,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(try_from = "TimeSheetEntryRaw")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "TimeSheetEntryJson")]
 pub struct TimeSheetEntry {
     pub description: String,
     pub start_time: DateTime<Local>,
@@ -227,7 +383,12 @@ impl TimeSheetEntry {
     // }
 
     fn duration_hours(&self) -> f64 {
-        self.duration_millis() as f64 / 3600000.0
+        self.hours_until(Local::now())
+    }
+
+    /// Hours from start to end, running entries counting until `now`.
+    pub fn hours_until(&self, now: DateTime<Local>) -> f64 {
+        (self.end_time.unwrap_or(now) - self.start_time).num_milliseconds() as f64 / 3600000.0
     }
 
     fn duration_millis(&self) -> i64 {
@@ -235,25 +396,8 @@ impl TimeSheetEntry {
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct TogglEntryRaw {
-    #[serde(alias = "Description")]
-    description: String,
-    #[serde(alias = "Start date")]
-    start_date: String,
-    #[serde(alias = "Start time")]
-    start_time: String,
-    #[serde(alias = "End date")]
-    end_date: String,
-    #[serde(alias = "End time")]
-    end_time: String,
-    // #[serde(alias = "Duration")]
-    // duration: String,
-    #[serde(alias = "Tags")]
-    tags: String,
-}
-
-#[derive(Debug, Deserialize)]
+/// Csv storage form of [`TimeSheetEntry`], with millisecond timestamps and flattened tags and properties.
+#[derive(Debug, Serialize, Deserialize)]
 struct TimeSheetEntryRaw {
     description: Option<String>,
     start_time: i64,
@@ -262,46 +406,6 @@ struct TimeSheetEntryRaw {
     properties: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct TimeSheetEntryFrontEnd {
-    description: String,
-    start_time: i64,
-    end_time: Option<i64>,
-    //TODO to array
-    tags: String,
-    properties: HashMap<String, String>,
-}
-
-const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
-impl TryFrom<TogglEntryRaw> for TimeSheetEntry {
-    type Error = &'static str;
-
-    fn try_from(value: TogglEntryRaw) -> Result<Self, Self::Error> {
-        let start_time = NaiveDateTime::parse_from_str(
-            &format!("{} {}", value.start_date, value.start_time),
-            DATE_FORMAT,
-        )
-        .unwrap() //TODO Use ?
-        .and_local_timezone(Local)
-        .unwrap();
-        let end_time = NaiveDateTime::parse_from_str(
-            &format!("{} {}", value.end_date, value.end_time),
-            DATE_FORMAT,
-        )
-        .unwrap()
-        .and_local_timezone(Local)
-        .unwrap();
-
-        Ok(TimeSheetEntry {
-            description: value.description,
-            start_time,
-            end_time: Some(end_time),
-            tags: value.tags.split(',').map(|s| s.to_string()).collect(),
-            properties: HashMap::new(),
-        })
-    }
-}
-
 impl TryFrom<TimeSheetEntryRaw> for TimeSheetEntry {
     type Error = &'static str;
 
@@ -343,82 +447,95 @@ impl TryFrom<TimeSheetEntryRaw> for TimeSheetEntry {
     }
 }
 
-impl TryFrom<TimeSheetEntryFrontEnd> for TimeSheetEntry {
-    type Error = &'static str;
-
-    fn try_from(value: TimeSheetEntryFrontEnd) -> Result<Self, Self::Error> {
-        let start_time = DateTime::from_timestamp_millis(value.start_time)
-            .unwrap() //TODO Use ?
-            .with_timezone(&Local);
-        let end_time = value.end_time.map(|et| {
-            DateTime::from_timestamp_millis(et)
-                .unwrap() //TODO Use ?
-                .with_timezone(&Local)
-        });
-
-        Ok(Self {
-            description: value.description,
-            start_time,
-            end_time,
-            tags: value.tags.split(',').map(|s| s.to_string()).collect(),
-            properties: value.properties,
-        })
-    }
-}
-
-impl From<TimeSheetEntry> for TimeSheetEntryFrontEnd {
-    fn from(entry: TimeSheetEntry) -> Self {
+impl From<&TimeSheetEntry> for TimeSheetEntryRaw {
+    fn from(entry: &TimeSheetEntry) -> Self {
+        //csv parser doesn't support HashMap
+        let mut properties = Vec::new();
+        for (k, v) in entry.properties.iter() {
+            properties.push(format!("{k}={v}"));
+        }
         Self {
-            description: entry.description,
+            description: Some(entry.description.clone()),
             start_time: entry.start_time.timestamp_millis(),
             end_time: entry.end_time.map(|dt| dt.timestamp_millis()),
-            tags: entry.tags.join(","),
-            properties: entry.properties,
+            tags: Some(entry.tags.join(",")),
+            properties: Some(properties.join(",")),
         }
     }
 }
 
-//TODO Separate csv and json serialization
+/// Json form, with RFC 3339 timestamps, native tags and properties, and the duration of finished entries.
 impl Serialize for TimeSheetEntry {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("TimeSheetEntry", 5)?;
+        let mut state = serializer.serialize_struct("TimeSheetEntry", 6)?;
         state.serialize_field("description", &self.description)?;
-        state.serialize_field("start_time", &self.start_time.timestamp_millis())?;
-        state.serialize_field("end_time", &self.end_time.map(|dt| dt.timestamp_millis()))?;
-        state.serialize_field("tags", &self.tags.join(","))?;
-        //csv parser doesn't support HashMap
-        let mut properties = Vec::new();
-        for (k, v) in self.properties.iter() {
-            properties.push(format!("{k}={v}"));
-        }
-        state.serialize_field("properties", &properties.join(","))?;
+        state.serialize_field("start_time", &self.start_time)?;
+        state.serialize_field("end_time", &self.end_time)?;
+        state.serialize_field(
+            "duration_millis",
+            &self.end_time.map(|_| self.duration_millis()),
+        )?;
+        state.serialize_field("tags", &self.tags)?;
+        state.serialize_field("properties", &self.properties)?;
         state.end()
     }
 }
 
-fn get_total_duration_for_date(date: &NaiveDate) -> f64 {
-    let entries = get_entries();
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TimeSheetEntryJson {
+    Native {
+        description: String,
+        start_time: DateTime<Local>,
+        end_time: Option<DateTime<Local>>,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        properties: HashMap<String, String>,
+    },
+    /// Journals, trash and event logs written when json used the csv form
+    Flat(TimeSheetEntryRaw),
+}
 
-    let mut total_hours = 0.0;
+impl TryFrom<TimeSheetEntryJson> for TimeSheetEntry {
+    type Error = &'static str;
 
-    let entries = entries
-        .into_iter()
-        .filter(|e| &e.start_time.date_naive() == date)
-        .collect::<Vec<TimeSheetEntry>>();
+    fn try_from(value: TimeSheetEntryJson) -> Result<Self, Self::Error> {
+        match value {
+            TimeSheetEntryJson::Native {
+                description,
+                start_time,
+                end_time,
+                tags,
+                properties,
+            } => Ok(TimeSheetEntry {
+                description,
+                start_time,
+                end_time,
+                tags,
+                properties,
+            }),
+            TimeSheetEntryJson::Flat(raw) => raw.try_into(),
+        }
+    }
+}
 
-    for entry in entries {
+fn get_total_duration_for_date(cache: &EntryCache, date: &NaiveDate) -> f64 {
+    let entries = cache.in_range(*date..=*date);
+
+    let mut total_hours = 0.0;
+
+    for entry in entries.iter() {
         total_hours += entry.duration_hours();
     }
 
     total_hours
 }
 
-fn get_total_duration_for_week() -> f64 {
-	let entries = get_entries();
-
+fn get_total_duration_for_week(cache: &EntryCache) -> f64 {
 	let mut total_hours = 0.0;
 	let mut monday = Local::now().date_naive();
 	let current_day = Local::now().date_naive().weekday();
@@ -427,12 +544,7 @@ fn get_total_duration_for_week() -> f64 {
 	}
 	let sunday = monday.checked_add_days(Days::new(6)).expect("Date overflow");
 
-	let entries = entries
-		.into_iter()
-		.filter(|e| &e.start_time.date_naive() >= &monday && &e.start_time.date_naive() <= &sunday)
-		.collect::<Vec<TimeSheetEntry>>();
-
-	for entry in entries {
+	for entry in cache.in_range(monday..=sunday) {
         total_hours += entry.duration_hours();
 	}
 
@@ -440,27 +552,20 @@ fn get_total_duration_for_week() -> f64 {
 }
 
 #[tauri::command]
-fn get_remaining_week_hours(holidays: u8) -> f64 {
-    (5 - holidays) as f64 * 8.0 - get_total_duration_for_week()
+fn get_remaining_week_hours(cache: &EntryCache, holidays: u8) -> f64 {
+    let today = Local::now().date_naive();
+    let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    (5 - holidays) as f64 * daily_target_hours(monday) - get_total_duration_for_week(cache)
 }
 
 pub fn purge_duplicates() {
 	use std::fs::File;
-	use std::io::{BufReader, BufWriter, BufRead, Write};
-	use csv::{Reader, Writer};
+	use std::io::{BufWriter, Write};
+	use csv::Writer;
 
 	let file_path = std::env::var("TIMESHEET_PATH").unwrap();
-	let header = {
-		let file = File::open(&file_path).unwrap();
-		BufReader::new(file).lines().next().unwrap().unwrap()
-	};
-	let file = File::open(&file_path).unwrap();
-	let mut reader = Reader::from_reader(BufReader::new(file));
-
-	let mut records = Vec::new();
-	for result in reader.records() {
-		records.push(result.unwrap());
-	}
+	format::upgrade_if_needed();
+	let format::Table { headers, mut records } = format::read_table(&file_path);
 
 	let mut seen = HashSet::new();
 	records.retain(|entry| seen.insert(entry.as_slice().to_string()));
@@ -469,38 +574,97 @@ pub fn purge_duplicates() {
 	let file = File::create(&file_path).unwrap();
 
 	let mut writer = BufWriter::new(file);
-	//WriterBuilder::has_header only works with serialize, not with write_record
-	writer.write_all(header.as_bytes()).unwrap();
-	writer.write_all(b"\n").unwrap();
+	writer.write_all(format::version_line().as_bytes()).unwrap();
 
 	let mut writer = Writer::from_writer(writer);
 
+	writer.write_record(&headers).unwrap();
 	for record in records {
 		writer.write_record(&record).unwrap();
 	}
 	writer.flush().unwrap();
 }
 
+/// Tests share `TIMESHEET_PATH` through the process environment, so they need to take turns.
+#[cfg(test)]
+pub(crate) fn lock_test_env() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::env;
-    use std::path::PathBuf;
+
+    #[test]
+    fn test_csv_and_json_forms() {
+        let start_time = DateTime::parse_from_rfc3339("2025-06-10T09:00:00+02:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let entry = TimeSheetEntry {
+            description: "Review".to_string(),
+            start_time,
+            end_time: Some(start_time + chrono::Duration::minutes(90)),
+            tags: vec!["dev".to_string(), "PROJ".to_string()],
+            properties: HashMap::from([("jira".to_string(), "PROJ-1".to_string())]),
+        };
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            DateTime::parse_from_rfc3339(json["start_time"].as_str().unwrap()).unwrap(),
+            start_time
+        );
+        assert_eq!(json["duration_millis"], 90 * 60 * 1000);
+        assert_eq!(json["tags"], serde_json::json!(["dev", "PROJ"]));
+        assert_eq!(json["properties"]["jira"], "PROJ-1");
+        assert_eq!(serde_json::from_value::<TimeSheetEntry>(json).unwrap(), entry);
+
+        // Written by earlier versions in journals, trash and event logs
+        let flat = serde_json::json!({
+            "description": "Review",
+            "start_time": start_time.timestamp_millis(),
+            "end_time": (start_time + chrono::Duration::minutes(90)).timestamp_millis(),
+            "tags": "dev,PROJ",
+            "properties": "jira=PROJ-1",
+        });
+        assert_eq!(serde_json::from_value::<TimeSheetEntry>(flat).unwrap(), entry);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("timesheet.csv");
+        write_timesheet(&path, std::slice::from_ref(&entry));
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "description,start_time,end_time,tags,properties",
+                "Review,1749538800000,1749544200000,\"dev,PROJ\",jira=PROJ-1",
+            ]
+        );
+        assert_eq!(read_timesheet(&path), vec![entry]);
+    }
 
     #[test]
     fn test_suggest_entry_descriptions() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
         let mut file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         file_path.push("tests/timesheet.csv");
-        env::set_var("TIMESHEET_PATH", &file_path);
+        // Loading upgrades the old format in place, so work on a copy
+        let dir = tempfile::tempdir().unwrap();
+        let copy_path = dir.path().join("timesheet.csv");
+        std::fs::copy(&file_path, &copy_path).unwrap();
+        env::set_var("TIMESHEET_PATH", &copy_path);
 
         // Should match 'work' (case-insensitive, deduped, most recent first)
-        let suggestions = suggest_entry_descriptions("work");
+        let suggestions = suggest_entry_descriptions(&cache, "work", None);
         assert_eq!(suggestions[0].description, "Work on project");
         assert_eq!(suggestions[0].tags, vec!["dev"]);
         assert_eq!(suggestions.len(), 1);
 
         // Should match 'e' (multiple, most recent first, max 5)
-        let suggestions = suggest_entry_descriptions("e");
+        let suggestions = suggest_entry_descriptions(&cache, "e", None);
         let expected = [
             ("Work on project", vec!["dev"]),
             ("Meeting", vec!["meeting"]),
@@ -515,7 +679,7 @@ mod tests {
         }
 
         // Should match nothing
-        let suggestions = suggest_entry_descriptions("xyz");
+        let suggestions = suggest_entry_descriptions(&cache, "xyz", None);
         assert_eq!(suggestions.len(), 0);
     }
 
@@ -533,38 +697,39 @@ mod tests {
 	// fn test_week_remaining_time() {
 	// 	dotenvy::dotenv().unwrap();
 
-    //     let remaining_hours = get_remaining_week_hours(1);
+    //     let remaining_hours = get_remaining_week_hours(&cache, 1);
 
 	// 	println!("Remaining hours: {:.2}", remaining_hours);
 	// }
 
 	#[test]
 	fn test_single_add_entry_no_duplication() {
-		use tempfile::NamedTempFile;
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
 		use chrono::{Local, Duration};
 		use std::fs::File;
-		use std::io::{BufRead, BufReader};
+		use std::io::BufReader;
 
-		// Create a temp file and set TIMESHEET_PATH
-		let temp_file = NamedTempFile::new().expect("Failed to create temp file");
-		let temp_path = temp_file.path().to_path_buf();
+		// Create a temp dir and set TIMESHEET_PATH
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		let temp_path = temp_dir.path().join("timesheet.csv");
 		std::env::set_var("TIMESHEET_PATH", &temp_path);
 
 		// Add a single entry
 		let now = Local::now();
-		let entry = TimeSheetEntryFrontEnd {
+		let entry = TimeSheetEntry {
 			description: "Single entry".to_string(),
-			start_time: now.timestamp_millis(),
-			end_time: Some((now + Duration::minutes(1)).timestamp_millis()),
-			tags: "test".to_string(),
+			start_time: now,
+			end_time: Some(now + Duration::minutes(1)),
+			tags: vec!["test".to_string()],
 			properties: Default::default(),
 		};
-		assert!(add_entry(entry));
+		assert!(add_entry(&cache, entry));
 
 		// Count lines in the CSV file
 		let file = File::open(&temp_path).expect("Failed to open temp csv");
 		let reader = BufReader::new(file);
-		let line_count = reader.lines().count();
+		let line_count = count_csv_lines(reader);
 
 		// Should be 2 (header + entry)
 		assert_eq!(line_count, 2, "CSV file should have 2 lines, but has {}. This indicates duplication bug.", line_count);
@@ -572,43 +737,44 @@ mod tests {
 
 	#[test]
 	fn test_add_multiple_entries_no_duplication() {
-		use tempfile::NamedTempFile;
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
 		use chrono::{Local, Duration};
 		use std::fs::File;
-		use std::io::{BufRead, BufReader};
+		use std::io::BufReader;
 
-		// Create a temp file and set TIMESHEET_PATH
-		let temp_file = NamedTempFile::new().expect("Failed to create temp file");
-		let temp_path = temp_file.path().to_path_buf();
+		// Create a temp dir and set TIMESHEET_PATH
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		let temp_path = temp_dir.path().join("timesheet.csv");
 		std::env::set_var("TIMESHEET_PATH", &temp_path);
 
 		// Add several unique entries
 		let now = Local::now();
 		for i in 0..3 {
-			let entry = TimeSheetEntryFrontEnd {
+			let entry = TimeSheetEntry {
 				description: format!("Entry {i}"),
-				start_time: (now + Duration::minutes(i)).timestamp_millis(),
-				end_time: Some((now + Duration::minutes(i+1)).timestamp_millis()),
-				tags: "test".to_string(),
+				start_time: now + Duration::minutes(i),
+				end_time: Some(now + Duration::minutes(i+1)),
+				tags: vec!["test".to_string()],
 				properties: Default::default(),
 			};
-			assert!(add_entry(entry));
+			assert!(add_entry(&cache, entry));
 		}
 
 		// Add one more entry
-		let entry = TimeSheetEntryFrontEnd {
+		let entry = TimeSheetEntry {
 			description: "Final entry".to_string(),
-			start_time: (now + Duration::minutes(10)).timestamp_millis(),
-			end_time: Some((now + Duration::minutes(11)).timestamp_millis()),
-			tags: "test".to_string(),
+			start_time: (now + Duration::minutes(10)),
+			end_time: Some(now + Duration::minutes(11)),
+			tags: vec!["test".to_string()],
 			properties: Default::default(),
 		};
-		assert!(add_entry(entry));
+		assert!(add_entry(&cache, entry));
 
 		// Count lines in the CSV file
 		let file = File::open(&temp_path).expect("Failed to open temp csv");
 		let reader = BufReader::new(file);
-		let line_count = reader.lines().count();
+		let line_count = count_csv_lines(reader);
 
 		// Should be 5 (not more)
 		assert_eq!(line_count, 5, "CSV file should have 5 lines, but has {}. This indicates exponential growth/duplication bug.", line_count);
@@ -616,41 +782,43 @@ mod tests {
 
 	#[test]
 	fn test_update_single_entry_no_duplication() {
-		use tempfile::NamedTempFile;
-		use chrono::{Local, Duration};
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
+		use chrono::{Local, Duration, SubsecRound};
 		use std::fs::File;
-		use std::io::{BufRead, BufReader};
+		use std::io::BufReader;
 
-		// Create a temp file and set TIMESHEET_PATH
-		let temp_file = NamedTempFile::new().expect("Failed to create temp file");
-		let temp_path = temp_file.path().to_path_buf();
+		// Create a temp dir and set TIMESHEET_PATH
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		let temp_path = temp_dir.path().join("timesheet.csv");
 		std::env::set_var("TIMESHEET_PATH", &temp_path);
 
 		// Add an entry
-		let now = Local::now();
-		let entry = TimeSheetEntryFrontEnd {
+		// Stored with millisecond precision
+		let now = Local::now().trunc_subsecs(3);
+		let entry = TimeSheetEntry {
 			description: "Original entry".to_string(),
-			start_time: now.timestamp_millis(),
-			end_time: Some((now + Duration::minutes(1)).timestamp_millis()),
-			tags: "test".to_string(),
+			start_time: now,
+			end_time: Some(now + Duration::minutes(1)),
+			tags: vec!["test".to_string()],
 			properties: Default::default(),
 		};
-		assert!(add_entry(entry.clone()));
+		assert!(add_entry(&cache, entry.clone()));
 
 		// Update the entry
-		let updated_entry = TimeSheetEntryFrontEnd {
+		let updated_entry = TimeSheetEntry {
 			description: "Updated entry".to_string(),
-			start_time: now.timestamp_millis(),
-			end_time: Some((now + Duration::minutes(2)).timestamp_millis()),
-			tags: "test".to_string(),
+			start_time: now,
+			end_time: Some(now + Duration::minutes(2)),
+			tags: vec!["test".to_string()],
 			properties: Default::default(),
 		};
-		assert!(update_entry(entry.description, entry.start_time, updated_entry));
+		assert!(update_entry(&cache, entry.description, entry.start_time, updated_entry));
 
 		// Count lines in the CSV file
 		let file = File::open(&temp_path).expect("Failed to open temp csv");
 		let reader = BufReader::new(file);
-		let line_count = reader.lines().count();
+		let line_count = count_csv_lines(reader);
 
 		// Should be 2 (header + updated entry)
 		assert_eq!(line_count, 2, "CSV file should have 2 lines, but has {}. This indicates duplication bug.", line_count);
@@ -658,47 +826,49 @@ mod tests {
 
 	#[test]
 	fn test_update_multiple_entries_no_duplication() {
-		use tempfile::NamedTempFile;
-		use chrono::{Local, Duration};
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
+		use chrono::{Local, Duration, SubsecRound};
 		use std::fs::File;
-		use std::io::{BufRead, BufReader};
+		use std::io::BufReader;
 
-		// Create a temp file and set TIMESHEET_PATH
-		let temp_file = NamedTempFile::new().expect("Failed to create temp file");
-		let temp_path = temp_file.path().to_path_buf();
+		// Create a temp dir and set TIMESHEET_PATH
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		let temp_path = temp_dir.path().join("timesheet.csv");
 		std::env::set_var("TIMESHEET_PATH", &temp_path);
 
 		// Add multiple entries
-		let now = Local::now();
+		// Stored with millisecond precision
+		let now = Local::now().trunc_subsecs(3);
 		let mut entries = Vec::new();
 		for i in 0..3 {
-			let entry = TimeSheetEntryFrontEnd {
+			let entry = TimeSheetEntry {
 				description: format!("Entry {i}"),
-				start_time: (now + Duration::minutes(i)).timestamp_millis(),
-				end_time: Some((now + Duration::minutes(i+1)).timestamp_millis()),
-				tags: "test".to_string(),
+				start_time: now + Duration::minutes(i),
+				end_time: Some(now + Duration::minutes(i+1)),
+				tags: vec!["test".to_string()],
 				properties: Default::default(),
 			};
-			assert!(add_entry(entry.clone()));
+			assert!(add_entry(&cache, entry.clone()));
 			entries.push(entry);
 		}
 
 		// Update the entries
 		for i in 0..entries.len() {
-			let updated_entry = TimeSheetEntryFrontEnd {
+			let updated_entry = TimeSheetEntry {
 				description: format!("Updated Entry {i}"),
-				start_time: (now + Duration::minutes(i as i64)).timestamp_millis(),
-				end_time: Some((now + Duration::minutes(i as i64 + 1)).timestamp_millis()),
-				tags: "test".to_string(),
+				start_time: now + Duration::minutes(i as i64),
+				end_time: Some(now + Duration::minutes(i as i64 + 1)),
+				tags: vec!["test".to_string()],
 				properties: Default::default(),
 			};
-			assert!(update_entry(entries[i].description.clone(), entries[i].start_time, updated_entry.clone()));
+			assert!(update_entry(&cache, entries[i].description.clone(), entries[i].start_time, updated_entry.clone()));
 		}
 
 		// Count lines in the CSV file
 		let file = File::open(&temp_path).expect("Failed to open temp csv");
 		let reader = BufReader::new(file);
-		let line_count = reader.lines().count();
+		let line_count = count_csv_lines(reader);
 
 		// Should be 4 (header + updated entries)
 		assert_eq!(line_count, 4, "CSV file should have 4 lines, but has {}. This indicates duplication bug.", line_count);
@@ -706,25 +876,26 @@ mod tests {
 
 	#[test]
 	fn test_get_entries_no_duplication() {
-		use tempfile::NamedTempFile;
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
 		use chrono::{Local, Duration};
 
-		// Create a temp file and set TIMESHEET_PATH
-		let temp_file = NamedTempFile::new().expect("Failed to create temp file");
-		let temp_path = temp_file.path().to_path_buf();
+		// Create a temp dir and set TIMESHEET_PATH
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		let temp_path = temp_dir.path().join("timesheet.csv");
 		std::env::set_var("TIMESHEET_PATH", &temp_path);
 
 		// Add multiple entries
 		let now = Local::now();
 		for i in 0..3 {
-			let entry = TimeSheetEntryFrontEnd {
+			let entry = TimeSheetEntry {
 				description: format!("Entry {i}"),
-				start_time: (now + Duration::minutes(i)).timestamp_millis(),
-				end_time: Some((now + Duration::minutes(i+1)).timestamp_millis()),
-				tags: "test".to_string(),
+				start_time: now + Duration::minutes(i),
+				end_time: Some(now + Duration::minutes(i+1)),
+				tags: vec!["test".to_string()],
 				properties: Default::default(),
 			};
-			assert!(add_entry(entry));
+			assert!(add_entry(&cache, entry));
 		}
 
 		// Get entries and check count
@@ -732,31 +903,37 @@ mod tests {
 		assert_eq!(entries.len(), 3, "get_entries should return 3 entries, but returned {}. This indicates duplication bug.", entries.len());
 	}
 
+	/// Lines of the csv itself, without the format version comment.
+	fn count_csv_lines(reader: impl std::io::BufRead) -> usize {
+		reader.lines().filter(|l| !l.as_ref().unwrap().starts_with("#format_version=")).count()
+	}
+
 	#[test]
 	fn test_purge_duplicates() {
-		use tempfile::NamedTempFile;
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
 		use chrono::{Local, Duration};
 		use std::fs::File;
-		use std::io::{BufRead, BufReader};
+		use std::io::BufReader;
 
-		// Create a temp file and set TIMESHEET_PATH
-		let temp_file = NamedTempFile::new().expect("Failed to create temp file");
-		let temp_path = temp_file.path().to_path_buf();
+		// Create a temp dir and set TIMESHEET_PATH
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		let temp_path = temp_dir.path().join("timesheet.csv");
 		std::env::set_var("TIMESHEET_PATH", &temp_path);
 
 		// Add multiple entries with some duplicates
 		let now = Local::now();
 		for i in 0..3 {
-			let entry = TimeSheetEntryFrontEnd {
+			let entry = TimeSheetEntry {
 				description: format!("Entry {i}"),
-				start_time: (now + Duration::minutes(i)).timestamp_millis(),
-				end_time: Some((now + Duration::minutes(i+1)).timestamp_millis()),
-				tags: "test".to_string(),
+				start_time: now + Duration::minutes(i),
+				end_time: Some(now + Duration::minutes(i+1)),
+				tags: vec!["test".to_string()],
 				properties: Default::default(),
 			};
-			assert!(add_entry(entry.clone()));
+			assert!(add_entry(&cache, entry.clone()));
 			// Add duplicate
-			assert!(add_entry(entry));
+			assert!(add_entry(&cache, entry));
 		}
 
 		// Purge duplicates
@@ -767,11 +944,46 @@ mod tests {
 		// Expect the file to have 4 lines
 		let file = File::open(&temp_path).expect("Failed to open temp csv");
 		let reader = BufReader::new(file);
-		let line_count = reader.lines().count();
+		let line_count = count_csv_lines(reader);
 		assert_eq!(line_count, 4, "CSV file should have 4 lines, but has {}.", line_count);
 
 		// Get entries and check count
 		let entries = get_entries();
 		assert_eq!(entries.len(), 3, "After purging duplicates, get_entries should return 3 entries, but returned {}. This indicates purge_duplicates is not working correctly.", entries.len());
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_delete_entry_in_event_log() {
+		let _env = lock_test_env();
+		let cache = EntryCache::new();
+		use chrono::{Local, Duration, SubsecRound};
+
+		let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+		std::env::set_var("TIMESHEET_PATH", temp_dir.path().join("timesheet.csv"));
+		std::env::set_var("EVENT_LOG_DIR", temp_dir.path().join("events"));
+		std::env::set_var("MACHINE_NAME", "laptop");
+
+		// The csv only has what was there when switching to the event log, with millisecond precision
+		let now = Local::now().trunc_subsecs(3);
+		let entry = |i: i64| TimeSheetEntry {
+			description: format!("Entry {i}"),
+			start_time: now + Duration::minutes(i),
+			end_time: Some(now + Duration::minutes(i + 1)),
+			tags: vec!["test".to_string()],
+			properties: Default::default(),
+		};
+		assert!(add_entry(&cache, entry(0)));
+		std::env::set_var("STORAGE_MODE", "eventlog");
+		for i in 1..4 {
+			assert!(add_entry(&cache, entry(i)));
+		}
+
+		assert!(delete_entry(&cache, "Entry 2".to_string(), entry(2).start_time));
+		let descriptions: Vec<String> = get_entries().into_iter().map(|e| e.description).collect();
+
+		std::env::remove_var("STORAGE_MODE");
+		std::env::remove_var("EVENT_LOG_DIR");
+		std::env::remove_var("MACHINE_NAME");
+		assert_eq!(descriptions, vec!["Entry 0", "Entry 1", "Entry 3"]);
+	}
+}
@@ -6,12 +6,181 @@ fn main() {
 
     //op run --env-file ../.env -- cargo run -- --add-worklogs
     if args.iter().find(|a| a.starts_with("--")).is_some() {
+        let cache = local_timesheet_lib::cache::EntryCache::new();
         if args.contains(&"--add-worklogs".to_string()) {
             dotenvy::dotenv().unwrap();
-            tokio::runtime::Runtime::new().unwrap().block_on(local_timesheet_lib::jira::add_missing_worklogs());
+            tokio::runtime::Runtime::new().unwrap().block_on(local_timesheet_lib::jira::add_missing_worklogs(&cache));
         }else if args.contains(&"--purge-duplicates".to_string()) {
 			dotenvy::dotenv().unwrap();
 			local_timesheet_lib::purge_duplicates();
+		}else if let Some(name) = args.iter().position(|a| a == "--start-template").and_then(|i| args.get(i + 1)) {
+			dotenvy::dotenv().unwrap();
+			if !local_timesheet_lib::templates::start_from_template(&cache, name.clone()) {
+				std::process::exit(1);
+			}
+		}else if args.contains(&"--undo".to_string()) || args.contains(&"--redo".to_string()) {
+			dotenvy::dotenv().unwrap();
+			let revert_worklog = args.contains(&"--revert-worklog".to_string());
+			let runtime = tokio::runtime::Runtime::new().unwrap();
+			let result = if args.contains(&"--undo".to_string()) {
+				runtime.block_on(local_timesheet_lib::journal::undo(&cache, revert_worklog))
+			} else {
+				runtime.block_on(local_timesheet_lib::journal::redo(&cache, revert_worklog))
+			};
+			if let Err(e) = result {
+				eprintln!("{e}");
+				std::process::exit(1);
+			}
+		}else if args.contains(&"--merge-conflicts".to_string()) {
+			dotenvy::dotenv().unwrap();
+			//--keep ours|theirs|both resolves every conflict the same way
+			let keep = match args.iter().position(|a| a == "--keep").map(|i| args.get(i + 1).map(|c| c.parse::<local_timesheet_lib::conflicts::Choice>())) {
+				None => None,
+				Some(Some(Ok(choice))) => Some(choice),
+				Some(Some(Err(e))) => {
+					eprintln!("{e}");
+					std::process::exit(1);
+				}
+				Some(None) => {
+					eprintln!("--keep expects ours, theirs or both");
+					std::process::exit(1);
+				}
+			};
+			let mut result = local_timesheet_lib::conflicts::merge_conflict_copies(&cache, vec![]);
+			if let (Some(choice), Ok(report)) = (keep, &result) {
+				if !report.conflicts.is_empty() {
+					let resolutions = report.conflicts.iter().map(|c| c.resolve(choice)).collect();
+					result = local_timesheet_lib::conflicts::merge_conflict_copies(&cache, resolutions);
+				}
+			}
+			let report = match result {
+				Ok(report) => report,
+				Err(e) => {
+					eprintln!("{e}");
+					std::process::exit(1);
+				}
+			};
+			for conflict in report.conflicts.iter() {
+				eprintln!("Conflict in {} for the entry starting at {}", conflict.copy, conflict.start_time);
+			}
+			if !report.conflicts.is_empty() {
+				std::process::exit(1);
+			}
+		}else if args.contains(&"--migrate-to-sqlite".to_string()) || args.contains(&"--migrate-to-csv".to_string()) {
+			dotenvy::dotenv().unwrap();
+			#[cfg(feature = "sqlite")]
+			if args.contains(&"--migrate-to-sqlite".to_string()) {
+				local_timesheet_lib::sqlite::migrate_to_sqlite();
+			} else {
+				local_timesheet_lib::sqlite::migrate_to_csv();
+			}
+			#[cfg(not(feature = "sqlite"))]
+			panic!("Migrating needs building with the sqlite feature");
+		}else if let Some(i) = args.iter().position(|a| a == "--export-toggl") {
+			dotenvy::dotenv().unwrap();
+			let [path, from, to] = [1, 2, 3].map(|offset| args.get(i + offset).expect("Usage: --export-toggl <path> <from> <to>").clone());
+			if let Err(e) = local_timesheet_lib::toggl::export_toggl(&cache, path, &from, &to) {
+				eprintln!("{e}");
+				std::process::exit(1);
+			}
+		}else if let Some(path) = args.iter().position(|a| a == "--import-toggl").and_then(|i| args.get(i + 1)) {
+			dotenvy::dotenv().unwrap();
+			match local_timesheet_lib::toggl::import_toggl(&cache, path.clone()) {
+				Ok(report) => println!("{} imported, {} duplicates, {} enriched", report.imported, report.duplicates, report.enriched),
+				Err(e) => {
+					eprintln!("{e}");
+					std::process::exit(1);
+				}
+			}
+		}else if let Some(i) = args.iter().position(|a| a == "--import-csv") {
+			dotenvy::dotenv().unwrap();
+			let (Some(path), Some(preset)) = (args.get(i + 1), args.get(i + 2)) else {
+				panic!("Usage: --import-csv <path> <toggl|clockify|harvest> [--dry-run]");
+			};
+			let mapping = local_timesheet_lib::import::get_import_presets().remove(preset).expect("Unknown import preset");
+			let dry_run = args.contains(&"--dry-run".to_string());
+			match local_timesheet_lib::import::import_csv(&cache, path.clone(), mapping, dry_run) {
+				Ok(preview) => {
+					for error in preview.errors.iter() {
+						eprintln!("{error}");
+					}
+					println!("{} new, {} duplicates, {} enriched", preview.summary.imported, preview.summary.duplicates, preview.summary.enriched);
+				}
+				Err(e) => {
+					eprintln!("{e}");
+					std::process::exit(1);
+				}
+			}
+		}else if let Some(i) = args.iter().position(|a| a == "--export-ics") {
+			dotenvy::dotenv().unwrap();
+			let [path, from, to] = [1, 2, 3].map(|offset| args.get(i + offset).expect("Usage: --export-ics <path> <from> <to>").clone());
+			if let Err(e) = local_timesheet_lib::ics::export_ics(&cache, path, &from, &to) {
+				eprintln!("{e}");
+				std::process::exit(1);
+			}
+		}else if let Some(path) = args.iter().position(|a| a == "--import-ics").and_then(|i| args.get(i + 1)) {
+			dotenvy::dotenv().unwrap();
+			match local_timesheet_lib::drafts::import_ics(&cache, path.clone()) {
+				Ok(report) => {
+					for draft in report.drafts.iter().filter(|d| d.overlaps) {
+						println!("Overlaps an entry: {} {}", draft.entry.start_time, draft.entry.description);
+					}
+					for event in report.skipped.iter() {
+						println!("Skipped {}: {}", event.summary, event.reason);
+					}
+					println!("{} drafts to review", report.drafts.len());
+				}
+				Err(e) => {
+					eprintln!("{e}");
+					std::process::exit(1);
+				}
+			}
+		}else if let Some(path) = args.iter().position(|a| a == "--export-json").and_then(|i| args.get(i + 1)) {
+			dotenvy::dotenv().unwrap();
+			println!("{} entries exported", local_timesheet_lib::json::export_json(&cache, path.clone()));
+		}else if let Some(path) = args.iter().position(|a| a == "--import-json").and_then(|i| args.get(i + 1)) {
+			dotenvy::dotenv().unwrap();
+			match local_timesheet_lib::json::import_json(&cache, path.clone()) {
+				Ok(report) => println!("{} imported, {} duplicates, {} enriched", report.imported, report.duplicates, report.enriched),
+				Err(e) => {
+					eprintln!("{e}");
+					std::process::exit(1);
+				}
+			}
+		}else if let Some(i) = args.iter().position(|a| a == "--export-excel") {
+			dotenvy::dotenv().unwrap();
+			let [path, from, to] = [1, 2, 3].map(|offset| args.get(i + offset).expect("Usage: --export-excel <path> <from> <to>").clone());
+			if let Err(e) = local_timesheet_lib::excel::export_excel(&cache, path, &from, &to) {
+				eprintln!("{e}");
+				std::process::exit(1);
+			}
+		}else if let Some(i) = args.iter().position(|a| a == "--report") {
+			dotenvy::dotenv().unwrap();
+			let usage = "Usage: --report <path.md|path.html|path.csv> <week|last-week|month|last-month|<from> <to>>";
+			let path = args.get(i + 1).expect(usage).clone();
+			let from = args.get(i + 2).expect(usage);
+			let (from, to) = match local_timesheet_lib::report::period(from, chrono::Local::now().date_naive()) {
+				Some((from, to)) => (from.to_string(), to.to_string()),
+				None => (from.clone(), args.get(i + 3).expect(usage).clone()),
+			};
+			if let Err(e) = local_timesheet_lib::report::export_report(&cache, path, &from, &to) {
+				eprintln!("{e}");
+				std::process::exit(1);
+			}
+		}else if let Some(i) = args.iter().position(|a| a == "--export-billing") {
+			dotenvy::dotenv().unwrap();
+			let [path, from, to] = [1, 2, 3].map(|offset| args.get(i + offset).expect("Usage: --export-billing <path> <from> <to>").clone());
+			if let Err(e) = local_timesheet_lib::billing::export_billing(&cache, path, &from, &to) {
+				eprintln!("{e}");
+				std::process::exit(1);
+			}
+		}else if args.contains(&"--budgets".to_string()) {
+			dotenvy::dotenv().unwrap();
+			for status in local_timesheet_lib::budgets::get_budget_status(&cache) {
+				let name = [status.budget.project.as_deref(), status.budget.jira.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+				let overrun = status.projected_overrun_date.map(|d| format!(", runs out {d}")).unwrap_or_default();
+				println!("{name}: {:.2}/{:.2} h ({:.0}%), {:.2} h/day{overrun}", status.consumed_hours, status.budget.hours, status.percent_used, status.burn_rate_hours_per_day);
+			}
 		}else {
             panic!("Unknown argument: {:?}", args);
         }
@@ -0,0 +1,333 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Datelike, Days, Duration, Local, NaiveDate, NaiveTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::cache::EntryCache;
+use crate::{daily_target_hours, TimeSheetEntry};
+
+/// How often the reminders are checked while the app runs.
+const CHECK_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+/// Source of the current time, so reminders can be tested at any time of day.
+pub trait Clock: Send {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReminderSettings {
+    /// When today's and this week's targets are reached
+    pub goals: bool,
+    /// When the running entry goes past this length
+    pub long_entry_minutes: Option<u32>,
+    /// When no timer ran for this long during working hours
+    pub idle_minutes: Option<u32>,
+    pub working_hours: (NaiveTime, NaiveTime),
+}
+
+impl ReminderSettings {
+    /// `REMINDER_GOALS` (`true` by default), `REMINDER_LONG_ENTRY_MINUTES`, `REMINDER_IDLE_MINUTES`
+    /// and `WORKING_HOURS` like `09:00-17:00`, the default.
+    pub fn from_env() -> Self {
+        let minutes = |var: &str| {
+            std::env::var(var).ok().map(|m| {
+                m.parse()
+                    .unwrap_or_else(|_| panic!("{var} should be a number of minutes"))
+            })
+        };
+        let working_hours =
+            std::env::var("WORKING_HOURS").unwrap_or_else(|_| "09:00-17:00".to_string());
+        let (start, end) = working_hours
+            .split_once('-')
+            .and_then(|(start, end)| {
+                let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok();
+                Some((parse(start)?, parse(end)?))
+            })
+            .expect("WORKING_HOURS should look like 09:00-17:00");
+
+        ReminderSettings {
+            goals: std::env::var("REMINDER_GOALS").map_or(true, |g| g != "false"),
+            long_entry_minutes: minutes("REMINDER_LONG_ENTRY_MINUTES"),
+            idle_minutes: minutes("REMINDER_IDLE_MINUTES"),
+            working_hours: (start, end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub title: String,
+    pub body: String,
+}
+
+/// Decides which reminders are due, each one given once until what it is about changes.
+pub struct Reminders<C: Clock> {
+    clock: C,
+    settings: ReminderSettings,
+    daily_goal_notified: Option<NaiveDate>,
+    /// Monday of the week notified
+    weekly_goal_notified: Option<NaiveDate>,
+    /// Start of the running entry notified
+    long_entry_notified: Option<DateTime<Local>>,
+    /// Start of the idle stretch notified
+    idle_notified: Option<DateTime<Local>>,
+}
+
+impl<C: Clock> Reminders<C> {
+    pub fn new(clock: C, settings: ReminderSettings) -> Self {
+        Reminders {
+            clock,
+            settings,
+            daily_goal_notified: None,
+            weekly_goal_notified: None,
+            long_entry_notified: None,
+            idle_notified: None,
+        }
+    }
+
+    pub fn check(&mut self, entries: &[TimeSheetEntry]) -> Vec<Reminder> {
+        let now = self.clock.now();
+        let mut reminders = Vec::new();
+        if self.settings.goals {
+            reminders.extend(self.check_goals(entries, now));
+        }
+        reminders.extend(self.check_long_entry(entries, now));
+        reminders.extend(self.check_idle(entries, now));
+
+        reminders
+    }
+
+    fn check_goals(&mut self, entries: &[TimeSheetEntry], now: DateTime<Local>) -> Vec<Reminder> {
+        let today = now.date_naive();
+        let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let hours_between = |from: NaiveDate, to: NaiveDate| -> f64 {
+            entries
+                .iter()
+                .filter(|e| (from..=to).contains(&e.start_time.date_naive()))
+                .map(|e| e.hours_until(now))
+                .sum()
+        };
+
+        let mut reminders = Vec::new();
+        let daily_target = daily_target_hours(today);
+        if daily_target > 0.0
+            && self.daily_goal_notified != Some(today)
+            && hours_between(today, today) >= daily_target
+        {
+            self.daily_goal_notified = Some(today);
+            reminders.push(Reminder {
+                title: "Daily goal reached".to_string(),
+                body: format!("{daily_target} hours logged today"),
+            });
+        }
+
+        let weekly_target: f64 = monday.iter_days().take(7).map(daily_target_hours).sum();
+        if weekly_target > 0.0
+            && self.weekly_goal_notified != Some(monday)
+            && hours_between(monday, monday + Days::new(6)) >= weekly_target
+        {
+            self.weekly_goal_notified = Some(monday);
+            reminders.push(Reminder {
+                title: "Weekly goal reached".to_string(),
+                body: format!("{weekly_target} hours logged this week"),
+            });
+        }
+
+        reminders
+    }
+
+    fn check_long_entry(
+        &mut self,
+        entries: &[TimeSheetEntry],
+        now: DateTime<Local>,
+    ) -> Option<Reminder> {
+        let limit = Duration::minutes(self.settings.long_entry_minutes? as i64);
+        let running = entries
+            .iter()
+            .find(|e| e.end_time.is_none() && e.start_time <= now)?;
+        if now - running.start_time < limit || self.long_entry_notified == Some(running.start_time)
+        {
+            return None;
+        }
+
+        self.long_entry_notified = Some(running.start_time);
+        Some(Reminder {
+            title: "Timer still running".to_string(),
+            body: format!(
+                "\"{}\" has been running for {} minutes",
+                running.description,
+                (now - running.start_time).num_minutes()
+            ),
+        })
+    }
+
+    fn check_idle(&mut self, entries: &[TimeSheetEntry], now: DateTime<Local>) -> Option<Reminder> {
+        let limit = Duration::minutes(self.settings.idle_minutes? as i64);
+        let (start, end) = self.settings.working_hours;
+        let today = now.date_naive();
+        if daily_target_hours(today) == 0.0 || now.time() < start || now.time() >= end {
+            return None;
+        }
+        if entries
+            .iter()
+            .any(|e| e.end_time.is_none() && e.start_time <= now)
+        {
+            return None;
+        }
+
+        let workday_start = today.and_time(start).and_local_timezone(Local).earliest()?;
+        let idle_since = entries
+            .iter()
+            .filter_map(|e| e.end_time)
+            .filter(|end| *end <= now)
+            .max()
+            .unwrap_or(workday_start)
+            .max(workday_start);
+        if now - idle_since < limit || self.idle_notified == Some(idle_since) {
+            return None;
+        }
+
+        self.idle_notified = Some(idle_since);
+        Some(Reminder {
+            title: "No timer running".to_string(),
+            body: format!(
+                "Nothing tracked for {} minutes",
+                (now - idle_since).num_minutes()
+            ),
+        })
+    }
+}
+
+/// Checks the reminders every minute in the background, showing them as system notifications.
+pub fn start(app: AppHandle) {
+    let mut reminders = Reminders::new(SystemClock, ReminderSettings::from_env());
+    std::thread::spawn(move || loop {
+        for reminder in reminders.check(&app.state::<EntryCache>().entries()) {
+            let shown = app
+                .notification()
+                .builder()
+                .title(&reminder.title)
+                .body(&reminder.body)
+                .show();
+            if let Err(e) = shown {
+                eprintln!("[ERROR] Failed to show notification: {e}");
+            }
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct TestClock(Arc<Mutex<DateTime<Local>>>);
+
+    impl Clock for TestClock {
+        fn now(&self) -> DateTime<Local> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    impl TestClock {
+        fn set(&self, hour: u32, minute: u32) {
+            *self.0.lock().unwrap() = at(hour, minute);
+        }
+    }
+
+    /// On Friday the 13th of June 2025
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2025, 6, 13, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn entry(start: DateTime<Local>, end: Option<DateTime<Local>>) -> TimeSheetEntry {
+        TimeSheetEntry {
+            description: "Work".to_string(),
+            start_time: start,
+            end_time: end,
+            tags: vec![],
+            properties: HashMap::new(),
+        }
+    }
+
+    fn titles(reminders: Vec<Reminder>) -> Vec<String> {
+        reminders.into_iter().map(|r| r.title).collect()
+    }
+
+    #[test]
+    fn test_reminders() {
+        let _env = crate::lock_test_env();
+        std::env::remove_var("DAILY_TARGET_HOURS");
+        let clock = TestClock(Arc::new(Mutex::new(at(9, 0))));
+        let settings = ReminderSettings {
+            goals: true,
+            long_entry_minutes: Some(120),
+            idle_minutes: Some(30),
+            working_hours: (
+                NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            ),
+        };
+        let mut reminders = Reminders::new(clock.clone(), settings);
+
+        // Monday to Thursday already done
+        let mut entries: Vec<TimeSheetEntry> = (9..13)
+            .map(|day| {
+                let start = Local.with_ymd_and_hms(2025, 6, day, 9, 0, 0).unwrap();
+                entry(start, Some(start + Duration::hours(8)))
+            })
+            .collect();
+        assert!(reminders.check(&entries).is_empty());
+
+        clock.set(9, 30);
+        assert_eq!(titles(reminders.check(&entries)), ["No timer running"]);
+        clock.set(9, 45);
+        assert!(reminders.check(&entries).is_empty(), "Reminded once");
+
+        entries.push(entry(at(9, 45), None));
+        clock.set(11, 45);
+        assert_eq!(titles(reminders.check(&entries)), ["Timer still running"]);
+        clock.set(12, 0);
+        assert!(reminders.check(&entries).is_empty());
+
+        entries.pop();
+        entries.push(entry(at(9, 45), Some(at(12, 0))));
+        clock.set(12, 20);
+        assert!(reminders.check(&entries).is_empty());
+        clock.set(12, 30);
+        assert_eq!(titles(reminders.check(&entries)), ["No timer running"]);
+
+        entries.push(entry(at(12, 30), None));
+        clock.set(18, 15);
+        assert_eq!(
+            titles(reminders.check(&entries)),
+            [
+                "Daily goal reached",
+                "Weekly goal reached",
+                "Timer still running"
+            ]
+        );
+        entries.pop();
+        entries.push(entry(at(12, 30), Some(at(18, 15))));
+        clock.set(18, 50);
+        assert!(
+            reminders.check(&entries).is_empty(),
+            "Goals given once, and no idle reminder after hours"
+        );
+    }
+}
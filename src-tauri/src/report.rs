@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::Serialize;
+use serde_json::Value;
+use tinytemplate::TinyTemplate;
+
+use crate::cache::EntryCache;
+use crate::rounding::rounded_durations;
+use crate::{daily_target_hours, parse_date, TimeSheetEntry};
+
+/// Hours spent on a project, tag or Jira issue.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub hours: f64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayTotal {
+    pub date: NaiveDate,
+    pub weekday: Weekday,
+    pub hours: f64,
+    pub target_hours: f64,
+    pub difference_hours: f64,
+}
+
+/// Totals of a range of days, what the report templates are rendered with.
+///
+/// Entries with several tags count towards each of them, so tag hours may add up to more than the total.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub total_hours: f64,
+    pub target_hours: f64,
+    pub difference_hours: f64,
+    pub days: Vec<DayTotal>,
+    pub projects: Vec<Group>,
+    pub tags: Vec<Group>,
+    pub issues: Vec<Group>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    Csv,
+}
+
+impl ReportFormat {
+    /// From the extension of the file the report is written to.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            "html" | "htm" => Some(ReportFormat::Html),
+            "csv" => Some(ReportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    fn template_name(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "report.md",
+            ReportFormat::Html => "report.html",
+            ReportFormat::Csv => "report.csv",
+        }
+    }
+
+    fn default_template(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => include_str!("../report_templates/report.md"),
+            ReportFormat::Html => include_str!("../report_templates/report.html"),
+            ReportFormat::Csv => include_str!("../report_templates/report.csv"),
+        }
+    }
+}
+
+/// Groups sorted by most hours first.
+fn to_groups(totals: HashMap<String, (f64, usize)>) -> Vec<Group> {
+    let mut groups: Vec<Group> = totals
+        .into_iter()
+        .map(|(name, (hours, entries))| Group {
+            name,
+            hours,
+            entries,
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.hours
+            .total_cmp(&a.hours)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    groups
+}
+
+/// Totals of the entries, expected to be the ones between `from` and `to`, rounded per `ROUNDING_MINUTES`.
+pub fn build_report<'a>(
+    from: NaiveDate,
+    to: NaiveDate,
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+) -> Report {
+    let mut day_hours: HashMap<NaiveDate, f64> = HashMap::new();
+    let (mut projects, mut tags, mut issues) = (HashMap::new(), HashMap::new(), HashMap::new());
+    let add = |totals: &mut HashMap<String, (f64, usize)>, name: &str, hours: f64| {
+        let total = totals.entry(name.to_string()).or_default();
+        total.0 += hours;
+        total.1 += 1;
+    };
+    for (entry, duration) in rounded_durations(entries) {
+        let hours = duration.num_milliseconds() as f64 / 3_600_000.0;
+        *day_hours.entry(entry.start_time.date_naive()).or_default() += hours;
+        let project = entry.properties.get("project").map(String::as_str);
+        add(&mut projects, project.unwrap_or("(no project)"), hours);
+        let entry_tags: Vec<&String> = entry.tags.iter().filter(|t| !t.is_empty()).collect();
+        if entry_tags.is_empty() {
+            add(&mut tags, "(untagged)", hours);
+        }
+        for tag in entry_tags {
+            add(&mut tags, tag, hours);
+        }
+        if let Some(issue) = entry.properties.get("jira") {
+            add(&mut issues, issue, hours);
+        }
+    }
+
+    let days: Vec<DayTotal> = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| {
+            let hours = day_hours.get(&date).copied().unwrap_or_default();
+            let target_hours = daily_target_hours(date);
+            DayTotal {
+                date,
+                weekday: date.weekday(),
+                hours,
+                target_hours,
+                difference_hours: hours - target_hours,
+            }
+        })
+        .collect();
+    let total_hours = days.iter().map(|d| d.hours).sum();
+    let target_hours = days.iter().map(|d| d.target_hours).sum();
+
+    Report {
+        from,
+        to,
+        total_hours,
+        target_hours,
+        difference_hours: total_hours - target_hours,
+        days,
+        projects: to_groups(projects),
+        tags: to_groups(tags),
+        issues: to_groups(issues),
+    }
+}
+
+/// `{ value | hours }` in templates, with two decimals.
+fn format_hours(value: &Value, output: &mut String) -> tinytemplate::error::Result<()> {
+    match value.as_f64() {
+        Some(hours) => output.push_str(&format!("{hours:.2}")),
+        None => tinytemplate::format_unescaped(value, output)?,
+    }
+    Ok(())
+}
+
+/// Keeps names with pipes from breaking Markdown tables.
+fn format_markdown(value: &Value, output: &mut String) -> tinytemplate::error::Result<()> {
+    let mut text = String::new();
+    tinytemplate::format_unescaped(value, &mut text)?;
+    output.push_str(&text.replace('|', "\\|").replace('\n', " "));
+    Ok(())
+}
+
+fn format_csv(value: &Value, output: &mut String) -> tinytemplate::error::Result<()> {
+    let mut text = String::new();
+    tinytemplate::format_unescaped(value, &mut text)?;
+    if text.contains([',', '"', '\n', '\r']) {
+        output.push_str(&format!("\"{}\"", text.replace('"', "\"\"")));
+    } else {
+        output.push_str(&text);
+    }
+    Ok(())
+}
+
+/// `REPORT_TEMPLATE_DIR`, where `report.md`, `report.html` and `report.csv` replace the built-in templates.
+fn custom_template(format: ReportFormat) -> Option<String> {
+    let dir = PathBuf::from(std::env::var("REPORT_TEMPLATE_DIR").ok()?);
+    std::fs::read_to_string(dir.join(format.template_name())).ok()
+}
+
+/// Renders the report with its template, values escaped for the format unless a formatter is given.
+pub fn render(report: &Report, format: ReportFormat) -> Result<String, String> {
+    let template = custom_template(format);
+    let text = template.as_deref().unwrap_or(format.default_template());
+
+    let mut tt = TinyTemplate::new();
+    match format {
+        ReportFormat::Markdown => tt.set_default_formatter(&format_markdown),
+        ReportFormat::Html => tt.set_default_formatter(&tinytemplate::format),
+        ReportFormat::Csv => tt.set_default_formatter(&format_csv),
+    }
+    tt.add_formatter("hours", format_hours);
+    tt.add_template(format.template_name(), text)
+        .map_err(|e| e.to_string())?;
+    tt.render(format.template_name(), report)
+        .map_err(|e| e.to_string())
+}
+
+/// First and last day of `week`, `last-week`, `month` or `last-month`, relative to `today`.
+pub fn period(name: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let first_of_month = today.with_day(1)?;
+    match name {
+        "week" => Some((monday, monday + Days::new(6))),
+        "last-week" => Some((monday - Days::new(7), monday - Days::new(1))),
+        "month" => {
+            let next_month = first_of_month.checked_add_months(chrono::Months::new(1))?;
+            Some((first_of_month, next_month.pred_opt()?))
+        }
+        "last-month" => {
+            let last_month = first_of_month.checked_sub_months(chrono::Months::new(1))?;
+            Some((last_month, first_of_month.pred_opt()?))
+        }
+        _ => None,
+    }
+}
+
+#[tauri::command]
+pub fn get_report(cache: &EntryCache, from: &str, to: &str) -> Result<Report, String> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+
+    let entries = cache.in_range(from..=to);
+    Ok(build_report(from, to, &entries))
+}
+
+/// Writes the report in the format given by the file extension.
+#[tauri::command]
+pub fn export_report(cache: &EntryCache, path: String, from: &str, to: &str) -> Result<(), String> {
+    let format = ReportFormat::from_path(Path::new(&path))
+        .ok_or("Report path should end with .md, .html or .csv")?;
+    let report = get_report(cache, from, to)?;
+    std::fs::write(&path, render(&report, format)?).map_err(|e| e.to_string())?;
+    println!("[INFO] Wrote report from {from} to {to} to {path}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local, TimeZone};
+
+    fn entry(
+        day: u32,
+        hour: u32,
+        minutes: i64,
+        tags: &[&str],
+        properties: &[(&str, &str)],
+    ) -> TimeSheetEntry {
+        let start_time = Local.with_ymd_and_hms(2025, 6, day, hour, 0, 0).unwrap();
+        TimeSheetEntry {
+            description: "Work".to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(minutes)),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            properties: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let _env = crate::lock_test_env();
+        std::env::remove_var("DAILY_TARGET_HOURS");
+        std::env::remove_var("REPORT_TEMPLATE_DIR");
+        let entries = [
+            entry(
+                9,
+                9,
+                360,
+                &["dev", "PROJ"],
+                &[("project", "Website, new"), ("jira", "PROJ-1")],
+            ),
+            entry(9, 15, 120, &["meeting"], &[("project", "Website, new")]),
+            entry(10, 9, 90, &[], &[("jira", "PROJ-2")]),
+            entry(14, 10, 60, &["dev"], &[("project", "A|B")]),
+        ];
+        let (from, to) = period("week", NaiveDate::from_ymd_opt(2025, 6, 11).unwrap()).unwrap();
+        assert_eq!((from.day(), to.day()), (9, 15));
+
+        let report = build_report(from, to, &entries);
+        assert_eq!(report.days.len(), 7);
+        assert_eq!(
+            (report.days[0].hours, report.days[0].difference_hours),
+            (8.0, 0.0)
+        );
+        assert_eq!(report.days[5].target_hours, 0.0, "Weekends have no target");
+        assert_eq!((report.total_hours, report.target_hours), (10.5, 40.0));
+        assert_eq!(report.projects[0].name, "Website, new");
+        assert_eq!(report.projects[0].entries, 2);
+        assert_eq!(
+            report.tags[0],
+            Group {
+                name: "dev".to_string(),
+                hours: 7.0,
+                entries: 2
+            }
+        );
+        assert!(report
+            .tags
+            .iter()
+            .any(|g| g.name == "(untagged)" && g.hours == 1.5));
+        assert_eq!(report.issues.len(), 2);
+
+        let markdown = render(&report, ReportFormat::Markdown).unwrap();
+        assert!(
+            markdown.contains("| 2025-06-09 Mon | 8.00 | 8.00 | 0.00 |"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("| A\\|B | 1.00 | 1 |"), "{markdown}");
+        assert!(markdown.contains("**Total:** 10.50 h of 40.00 h target (-29.50 h)"));
+        let csv = render(&report, ReportFormat::Csv).unwrap();
+        assert!(
+            csv.contains("\nProject,\"Website, new\",8.00,,2\n"),
+            "{csv}"
+        );
+        assert!(csv.ends_with("Total,,10.50,40.00,\n"), "{csv}");
+        let html = render(&report, ReportFormat::Html).unwrap();
+        assert!(
+            html.contains("<td>PROJ-1</td><td class=\"number\">6.00</td>"),
+            "{html}"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("report.md"),
+            "{total_hours | hours} / {target_hours}",
+        )
+        .unwrap();
+        std::env::set_var("REPORT_TEMPLATE_DIR", dir.path());
+        assert_eq!(
+            render(&report, ReportFormat::Markdown).unwrap(),
+            "10.50 / 40.0"
+        );
+        std::env::remove_var("REPORT_TEMPLATE_DIR");
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::TimeSheetEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingMode {
+    Nearest,
+    Up,
+    Down,
+}
+
+/// Whether each entry is rounded, or the total of each day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingScope {
+    Entry,
+    Day,
+}
+
+/// How durations are rounded in reports, exports and Jira worklogs. The timesheet keeps the exact times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rounding {
+    pub mode: RoundingMode,
+    /// Usually 1, 5, 6 or 15
+    pub minutes: u32,
+    pub scope: RoundingScope,
+}
+
+impl Rounding {
+    /// `ROUNDING_MINUTES`, with `ROUNDING_MODE` (`nearest`, `up` or `down`, `nearest` by default)
+    /// and `ROUNDING_SCOPE` (`entry` or `day`, `entry` by default).
+    ///
+    /// `None` when `ROUNDING_MINUTES` isn't set, durations are then used as they are.
+    pub fn from_env() -> Option<Self> {
+        let minutes: u32 = std::env::var("ROUNDING_MINUTES")
+            .ok()?
+            .parse()
+            .expect("ROUNDING_MINUTES should be a number of minutes");
+        assert!(minutes > 0, "ROUNDING_MINUTES should be at least 1");
+        let mode = match std::env::var("ROUNDING_MODE").as_deref() {
+            Ok("up") => RoundingMode::Up,
+            Ok("down") => RoundingMode::Down,
+            Ok("nearest") | Err(_) => RoundingMode::Nearest,
+            Ok(other) => panic!("Unknown ROUNDING_MODE: {other}"),
+        };
+        let scope = match std::env::var("ROUNDING_SCOPE").as_deref() {
+            Ok("day") => RoundingScope::Day,
+            Ok("entry") | Err(_) => RoundingScope::Entry,
+            Ok(other) => panic!("Unknown ROUNDING_SCOPE: {other}"),
+        };
+
+        Some(Rounding {
+            mode,
+            minutes,
+            scope,
+        })
+    }
+
+    fn increment(&self) -> i64 {
+        self.minutes as i64 * 60
+    }
+
+    pub fn round(&self, duration: Duration) -> Duration {
+        let (seconds, increment) = (duration.num_seconds(), self.increment());
+        let increments = match self.mode {
+            RoundingMode::Nearest => (seconds + increment / 2).div_euclid(increment),
+            RoundingMode::Up => (seconds + increment - 1).div_euclid(increment),
+            RoundingMode::Down => seconds.div_euclid(increment),
+        };
+        Duration::seconds(increments * increment)
+    }
+
+    /// Rounded durations of the entries, in the same order.
+    ///
+    /// With the day scope, each day's total is rounded and split back over its entries,
+    /// the increments left after rounding them down going to the ones closest to the next.
+    pub fn apply(&self, entries: &[&TimeSheetEntry]) -> Vec<Duration> {
+        let exact: Vec<Duration> = entries
+            .iter()
+            .map(|e| Duration::milliseconds(e.duration_millis()))
+            .collect();
+        if self.scope == RoundingScope::Entry {
+            return exact.into_iter().map(|d| self.round(d)).collect();
+        }
+
+        let mut rounded = exact.clone();
+        let mut days: HashMap<NaiveDate, Vec<usize>> = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            days.entry(entry.start_time.date_naive())
+                .or_default()
+                .push(i);
+        }
+        for indices in days.into_values() {
+            let day: Vec<Duration> = indices.iter().map(|&i| exact[i]).collect();
+            let total = self.round(day.iter().sum());
+            for (i, duration) in indices.into_iter().zip(self.split(total, &day)) {
+                rounded[i] = duration;
+            }
+        }
+
+        rounded
+    }
+
+    /// Splits `total` over durations in increments, rounding them down and giving what is left
+    /// to the ones closest to the next increment, or taking from the furthest when there is less.
+    pub fn split(&self, total: Duration, exact: &[Duration]) -> Vec<Duration> {
+        let increment = self.increment();
+        let mut rounded: Vec<Duration> = exact
+            .iter()
+            .map(|d| Duration::seconds(d.num_seconds().div_euclid(increment) * increment))
+            .collect();
+        let floors: Duration = rounded.iter().sum();
+        let mut extra = (total - floors).num_seconds() / increment;
+        let mut order: Vec<usize> = (0..exact.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(exact[i] - rounded[i]));
+
+        for &i in order.iter().take(extra.max(0) as usize) {
+            rounded[i] += Duration::seconds(increment);
+        }
+        for &i in order.iter().rev() {
+            if extra >= 0 {
+                break;
+            }
+            let taken = (rounded[i].num_seconds() / increment).min(-extra);
+            rounded[i] -= Duration::seconds(taken * increment);
+            extra += taken;
+        }
+
+        rounded
+    }
+}
+
+/// The entries with their durations, rounded per `ROUNDING_MINUTES` when set.
+pub fn rounded_durations<'a>(
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+) -> Vec<(&'a TimeSheetEntry, Duration)> {
+    let entries: Vec<&TimeSheetEntry> = entries.into_iter().collect();
+    let durations = match Rounding::from_env() {
+        Some(rounding) => rounding.apply(&entries),
+        None => entries
+            .iter()
+            .map(|e| Duration::milliseconds(e.duration_millis()))
+            .collect(),
+    };
+
+    entries.into_iter().zip(durations).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn entry(day: u32, hour: u32, minutes: i64) -> TimeSheetEntry {
+        let start_time = Local.with_ymd_and_hms(2025, 6, day, hour, 0, 0).unwrap();
+        TimeSheetEntry {
+            description: "Work".to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(minutes)),
+            tags: vec![],
+            properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_rounding() {
+        let rounding = |mode, minutes, scope| Rounding {
+            mode,
+            minutes,
+            scope,
+        };
+        let entries = [
+            entry(9, 9, 7),
+            entry(9, 10, 7),
+            entry(9, 11, 53),
+            entry(10, 9, 8),
+        ];
+        let entries: Vec<&TimeSheetEntry> = entries.iter().collect();
+        let minutes = |durations: Vec<Duration>| -> Vec<i64> {
+            durations.iter().map(|d| d.num_minutes()).collect()
+        };
+
+        use RoundingMode::*;
+        use RoundingScope::*;
+        assert_eq!(
+            minutes(rounding(Nearest, 15, Entry).apply(&entries)),
+            [0, 0, 60, 15]
+        );
+        assert_eq!(
+            minutes(rounding(Up, 15, Entry).apply(&entries)),
+            [15, 15, 60, 15]
+        );
+        assert_eq!(
+            minutes(rounding(Down, 6, Entry).apply(&entries)),
+            [6, 6, 48, 6]
+        );
+        assert_eq!(
+            minutes(rounding(Nearest, 1, Entry).apply(&entries)),
+            [7, 7, 53, 8]
+        );
+
+        // 67 minutes on the 9th round to 60, the increment left going to the 53 minutes entry
+        assert_eq!(
+            minutes(rounding(Nearest, 15, Day).apply(&entries)),
+            [0, 0, 60, 15]
+        );
+        assert_eq!(
+            minutes(rounding(Up, 15, Day).apply(&entries)),
+            [15, 0, 60, 15]
+        );
+        assert_eq!(
+            minutes(rounding(Down, 15, Day).apply(&entries)),
+            [0, 0, 60, 0]
+        );
+        assert_eq!(
+            rounding(Nearest, 15, Entry).round(Duration::seconds(450)),
+            Duration::minutes(15),
+            "Halfway rounds up"
+        );
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction};
+
+use crate::cache::EntryCache;
+use crate::{eventlog, read_timesheet, timesheet_sidecar_path, write_timesheet, TimeSheetEntry};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    description TEXT NOT NULL,
+    start_time INTEGER NOT NULL,
+    end_time INTEGER
+);
+CREATE INDEX IF NOT EXISTS entries_start_time ON entries (start_time);
+
+CREATE TABLE IF NOT EXISTS entry_tags (
+    entry_id INTEGER NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (entry_id, position)
+);
+CREATE INDEX IF NOT EXISTS entry_tags_tag ON entry_tags (tag);
+
+CREATE TABLE IF NOT EXISTS entry_properties (
+    entry_id INTEGER NOT NULL REFERENCES entries (id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (entry_id, key)
+);
+CREATE INDEX IF NOT EXISTS entry_properties_key_value ON entry_properties (key, value);
+";
+
+/// `TIMESHEET_DB_PATH`, or `<timesheet>.sqlite` next to `TIMESHEET_PATH`.
+pub fn db_path() -> PathBuf {
+    match std::env::var("TIMESHEET_DB_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("sqlite"),
+    }
+}
+
+fn open() -> Connection {
+    let connection = Connection::open(db_path()).expect("Timesheet database should open");
+    connection
+        .execute_batch("PRAGMA foreign_keys = ON;")
+        .unwrap();
+    connection.execute_batch(SCHEMA).unwrap();
+    connection
+}
+
+fn to_datetime(millis: i64) -> DateTime<Local> {
+    DateTime::from_timestamp_millis(millis)
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+fn insert(tx: &Transaction, entry: &TimeSheetEntry) {
+    tx.execute(
+        "INSERT INTO entries (description, start_time, end_time) VALUES (?1, ?2, ?3)",
+        params![
+            entry.description,
+            entry.start_time.timestamp_millis(),
+            entry.end_time.map(|t| t.timestamp_millis())
+        ],
+    )
+    .unwrap();
+    insert_details(tx, tx.last_insert_rowid(), entry);
+}
+
+fn replace(tx: &Transaction, id: i64, entry: &TimeSheetEntry) {
+    tx.execute(
+        "UPDATE entries SET description = ?1, start_time = ?2, end_time = ?3 WHERE id = ?4",
+        params![
+            entry.description,
+            entry.start_time.timestamp_millis(),
+            entry.end_time.map(|t| t.timestamp_millis()),
+            id
+        ],
+    )
+    .unwrap();
+    tx.execute("DELETE FROM entry_tags WHERE entry_id = ?1", [id])
+        .unwrap();
+    tx.execute("DELETE FROM entry_properties WHERE entry_id = ?1", [id])
+        .unwrap();
+    insert_details(tx, id, entry);
+}
+
+fn insert_details(tx: &Transaction, id: i64, entry: &TimeSheetEntry) {
+    for (position, tag) in entry.tags.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO entry_tags (entry_id, position, tag) VALUES (?1, ?2, ?3)",
+            params![id, position as i64, tag],
+        )
+        .unwrap();
+    }
+    for (key, value) in entry.properties.iter() {
+        tx.execute(
+            "INSERT INTO entry_properties (entry_id, key, value) VALUES (?1, ?2, ?3)",
+            params![id, key, value],
+        )
+        .unwrap();
+    }
+}
+
+/// Entries matching the `WHERE` clause over `entries`, in insertion order.
+fn query(
+    connection: &Connection,
+    filter: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Vec<TimeSheetEntry> {
+    query_with_ids(connection, filter, params)
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Like [`query`], along with their row ids.
+fn query_with_ids(
+    connection: &Connection,
+    filter: &str,
+    params: &[&dyn rusqlite::ToSql],
+) -> Vec<(i64, TimeSheetEntry)> {
+    let mut statement = connection
+        .prepare(&format!(
+            "SELECT id, description, start_time, end_time FROM entries WHERE {filter} ORDER BY id"
+        ))
+        .unwrap();
+    let rows: Vec<(i64, TimeSheetEntry)> = statement
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get(0)?,
+                TimeSheetEntry {
+                    description: row.get(1)?,
+                    start_time: to_datetime(row.get(2)?),
+                    end_time: row.get::<_, Option<i64>>(3)?.map(to_datetime),
+                    tags: Vec::new(),
+                    properties: HashMap::new(),
+                },
+            ))
+        })
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect();
+
+    let mut tags = connection
+        .prepare("SELECT tag FROM entry_tags WHERE entry_id = ?1 ORDER BY position")
+        .unwrap();
+    let mut properties = connection
+        .prepare("SELECT key, value FROM entry_properties WHERE entry_id = ?1")
+        .unwrap();
+    rows.into_iter()
+        .map(|(id, mut entry)| {
+            entry.tags = tags
+                .query_map([id], |row| row.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            entry.properties = properties
+                .query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            (id, entry)
+        })
+        .collect()
+}
+
+pub fn load() -> Vec<TimeSheetEntry> {
+    query(&open(), "1", &[])
+}
+
+/// Entries that started within `[start, end)`.
+pub fn entries_between(start: DateTime<Local>, end: DateTime<Local>) -> Vec<TimeSheetEntry> {
+    query(
+        &open(),
+        "start_time >= ?1 AND start_time < ?2",
+        &[&start.timestamp_millis(), &end.timestamp_millis()],
+    )
+}
+
+/// Entries that started on the local dates, in insertion order.
+pub fn entries_on_dates(dates: RangeInclusive<NaiveDate>) -> Vec<TimeSheetEntry> {
+    // A day wider on both sides whatever the time zone, then narrowed to the local dates
+    let start = dates.start().and_time(NaiveTime::MIN).and_utc() - Days::new(1);
+    let end = dates.end().and_time(NaiveTime::MIN).and_utc() + Days::new(2);
+    entries_between(start.with_timezone(&Local), end.with_timezone(&Local))
+        .into_iter()
+        .filter(|e| dates.contains(&e.start_time.date_naive()))
+        .collect()
+}
+
+pub fn entries_with_tag(tag: &str) -> Vec<TimeSheetEntry> {
+    query(
+        &open(),
+        "id IN (SELECT entry_id FROM entry_tags WHERE tag = ?1)",
+        &[&tag],
+    )
+}
+
+pub fn entries_with_property(key: &str, value: &str) -> Vec<TimeSheetEntry> {
+    query(
+        &open(),
+        "id IN (SELECT entry_id FROM entry_properties WHERE key = ?1 AND value = ?2)",
+        &[&key, &value],
+    )
+}
+
+pub fn insert_entry(cache: &EntryCache, entry: &TimeSheetEntry) {
+    let mut connection = open();
+    let tx = connection.transaction().unwrap();
+    insert(&tx, entry);
+    tx.commit().unwrap();
+    cache.invalidate();
+}
+
+/// Replaces the entry in place, returning the previous version if it was found.
+pub fn update_entry(
+    cache: &EntryCache,
+    old_description: &str,
+    old_start_time: DateTime<Local>,
+    entry: &TimeSheetEntry,
+) -> Option<TimeSheetEntry> {
+    let mut connection = open();
+    let tx = connection.transaction().unwrap();
+    let id: i64 = tx
+        .query_row(
+            "SELECT id FROM entries WHERE description = ?1 AND start_time = ?2 ORDER BY id LIMIT 1",
+            params![old_description, old_start_time.timestamp_millis()],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap()?;
+    let old = query(&tx, "id = ?1", &[&id]).pop();
+
+    replace(&tx, id, entry);
+    tx.commit().unwrap();
+    cache.invalidate();
+
+    old
+}
+
+/// Deletes every entry with this description and start time, returning them.
+pub fn delete_entries(
+    cache: &EntryCache,
+    description: &str,
+    start_time: DateTime<Local>,
+) -> Vec<TimeSheetEntry> {
+    let mut connection = open();
+    let tx = connection.transaction().unwrap();
+    let filter = "description = ?1 AND start_time = ?2";
+    let params: [&dyn rusqlite::ToSql; 2] = [&description, &start_time.timestamp_millis()];
+    let deleted = query(&tx, filter, &params);
+    tx.execute(&format!("DELETE FROM entries WHERE {filter}"), &params[..])
+        .unwrap();
+    tx.commit().unwrap();
+    cache.invalidate();
+
+    deleted
+}
+
+/// Turns the stored entries into `entries` for changes spanning many entries like undo or restores,
+/// only writing the rows that changed.
+///
+/// Entries are matched to the stored ones like in the event log, new ones being added at the end.
+pub fn save(entries: &[TimeSheetEntry]) {
+    let mut connection = open();
+    let tx = connection.transaction().unwrap();
+    let current = query_with_ids(&tx, "1", &[]);
+    let stored: Vec<&TimeSheetEntry> = current.iter().map(|(_, entry)| entry).collect();
+
+    let mut kept = vec![false; current.len()];
+    for (entry, matched) in entries
+        .iter()
+        .zip(eventlog::match_entries(&stored, entries))
+    {
+        match matched {
+            Some(i) => {
+                kept[i] = true;
+                if current[i].1 != *entry {
+                    replace(&tx, current[i].0, entry);
+                }
+            }
+            None => insert(&tx, entry),
+        }
+    }
+    for ((id, _), kept) in current.iter().zip(kept) {
+        if !kept {
+            tx.execute("DELETE FROM entries WHERE id = ?1", [id])
+                .unwrap();
+        }
+    }
+    tx.commit().unwrap();
+}
+
+/// Copies the csv timesheet at `TIMESHEET_PATH` into the database, replacing its content.
+pub fn migrate_to_sqlite() -> usize {
+    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
+    let entries = read_timesheet(&timesheet_path);
+    save(&entries);
+
+    println!(
+        "[INFO] Migrated {} entries from {timesheet_path} to {}",
+        entries.len(),
+        db_path().display()
+    );
+
+    entries.len()
+}
+
+/// Writes the database's entries back to the csv timesheet, backing up the one being replaced.
+pub fn migrate_to_csv() -> usize {
+    let timesheet_path = std::env::var("TIMESHEET_PATH").unwrap();
+    let entries = load();
+    crate::backup::snapshot();
+    write_timesheet(&timesheet_path, &entries);
+
+    println!(
+        "[INFO] Migrated {} entries from {} to {timesheet_path}",
+        entries.len(),
+        db_path().display()
+    );
+
+    entries.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lock_test_env, StorageMode};
+    use chrono::Duration;
+
+    fn entry(description: &str, start_time: DateTime<Local>, tags: &[&str]) -> TimeSheetEntry {
+        TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(30)),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            properties: HashMap::from([("jira".to_string(), "PROJ-1".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_entry_operations() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TIMESHEET_DB_PATH");
+
+        let now = to_datetime(Local::now().timestamp_millis());
+        insert_entry(&cache, &entry("First", now, &["b", "a"]));
+        insert_entry(&cache, &entry("Second", now + Duration::hours(1), &["a"]));
+
+        let mut updated = entry("First, renamed", now, &["c"]);
+        updated.end_time = None;
+        let old = update_entry(&cache, "First", now, &updated).unwrap();
+        assert_eq!(old.tags, vec!["b", "a"]);
+        assert!(update_entry(&cache, "Missing", now, &updated).is_none());
+        assert_eq!(
+            load(),
+            vec![
+                updated.clone(),
+                entry("Second", now + Duration::hours(1), &["a"])
+            ]
+        );
+
+        assert_eq!(entries_with_tag("a").len(), 1);
+        assert_eq!(entries_with_property("jira", "PROJ-1").len(), 2);
+        assert_eq!(
+            entries_between(now, now + Duration::minutes(1)),
+            vec![updated]
+        );
+
+        assert_eq!(
+            delete_entries(&cache, "Second", now + Duration::hours(1)).len(),
+            1
+        );
+        assert_eq!(load().len(), 1);
+        assert_eq!(
+            std::fs::read_dir(dir.path()).unwrap().count(),
+            1,
+            "Only the database is written"
+        );
+    }
+
+    #[test]
+    fn test_save_only_writes_changes() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TIMESHEET_DB_PATH");
+        std::env::remove_var("TOGGL_SHEET_PATH");
+
+        let now = to_datetime(Local::now().timestamp_millis());
+        for (i, description) in ["First", "Second", "Third"].into_iter().enumerate() {
+            insert_entry(
+                &cache,
+                &entry(description, now + Duration::hours(i as i64), &["a"]),
+            );
+        }
+        let ids = |connection: &Connection| -> Vec<i64> {
+            query_with_ids(connection, "1", &[])
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect()
+        };
+        let before = ids(&open());
+
+        let mut entries = load();
+        entries[0].tags = vec!["b".to_string()];
+        entries.remove(1);
+        entries.push(entry("Fourth", now + Duration::hours(3), &[]));
+        save(&entries);
+        assert_eq!(load(), entries);
+        let after = ids(&open());
+        assert_eq!(
+            after[..2],
+            [before[0], before[2]],
+            "Rows are updated in place"
+        );
+        assert!(after[2] > before[2]);
+
+        std::env::set_var("STORAGE_MODE", "sqlite");
+        assert_eq!(
+            cache.in_range(now.date_naive()..=now.date_naive()),
+            entries
+                .iter()
+                .filter(|e| e.start_time.date_naive() == now.date_naive())
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(cache.with_property("jira", "PROJ-1").len(), 3);
+        std::env::remove_var("STORAGE_MODE");
+    }
+
+    #[test]
+    fn test_lossless_migration() {
+        let _env = lock_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        let timesheet_path = dir.path().join("timesheet.csv");
+        std::env::set_var("TIMESHEET_PATH", &timesheet_path);
+        std::env::set_var("TIMESHEET_DB_PATH", dir.path().join("other.db"));
+
+        let now = to_datetime(Local::now().timestamp_millis());
+        let mut running = entry(
+            "Running, \"quoted\"",
+            now + Duration::hours(1),
+            &["z", "a", "m"],
+        );
+        running.end_time = None;
+        write_timesheet(&timesheet_path, &[entry("Done", now, &[]), running]);
+        let entries = read_timesheet(&timesheet_path);
+
+        assert_eq!(migrate_to_sqlite(), 2);
+        std::fs::remove_file(&timesheet_path).unwrap();
+        std::env::set_var("STORAGE_MODE", "sqlite");
+        assert_eq!(StorageMode::from_env(), StorageMode::Sqlite);
+        assert_eq!(crate::get_entries(), entries);
+        std::env::remove_var("STORAGE_MODE");
+
+        assert_eq!(migrate_to_csv(), 2);
+        assert_eq!(read_timesheet(&timesheet_path), entries);
+        std::env::remove_var("TIMESHEET_DB_PATH");
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+use crate::{TimeSheetEntry, TimeSheetEntryTemplate};
+
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 5;
+
+/// Days after which an entry's weight in the frecency score is halved.
+const RECENCY_HALF_LIFE_DAYS: f64 = 14.0;
+
+/// All past entries sharing a description and tag set, scored as one suggestion.
+struct Candidate<'a> {
+    latest: &'a TimeSheetEntry,
+    description: String,
+    frecency: f64,
+    same_time_of_day: u32,
+    same_weekday: u32,
+}
+
+/// Ranks past entries against what has been typed so far.
+///
+/// The description is fuzzy matched (in-order characters, favoring prefixes, word starts and
+/// contiguous runs), while tags and the `jira` property only match by prefix.
+/// Matches are then boosted by how often and how recently they were used,
+/// and by whether they usually happen around this hour or on this weekday.
+pub fn rank_suggestions(
+    entries: &[TimeSheetEntry],
+    partial: &str,
+    now: DateTime<Local>,
+    limit: usize,
+) -> Vec<TimeSheetEntryTemplate> {
+    let partial = partial.trim().to_lowercase();
+
+    let mut candidates: HashMap<(String, BTreeSet<String>), Candidate> = HashMap::new();
+    for entry in entries {
+        let description = entry.description.trim().to_lowercase();
+        let tags: BTreeSet<String> = entry.tags.iter().map(|s| s.trim().to_lowercase()).collect();
+
+        let age_days = (now - entry.start_time).num_minutes().max(0) as f64 / (60.0 * 24.0);
+        let weight = 0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+        let hour_distance = (entry.start_time.hour() as i32 - now.hour() as i32).abs();
+
+        let candidate = candidates
+            .entry((description.clone(), tags))
+            .or_insert_with(|| Candidate {
+                latest: entry,
+                description,
+                frecency: 0.0,
+                same_time_of_day: 0,
+                same_weekday: 0,
+            });
+        if entry.start_time > candidate.latest.start_time {
+            candidate.latest = entry;
+        }
+        candidate.frecency += weight;
+        if hour_distance <= 1 || hour_distance >= 23 {
+            candidate.same_time_of_day += 1;
+        }
+        if entry.start_time.weekday() == now.weekday() {
+            candidate.same_weekday += 1;
+        }
+    }
+
+    let mut scored: Vec<(f64, &Candidate)> = candidates
+        .values()
+        .filter_map(|candidate| {
+            let match_score = match_score(&partial, candidate)?;
+            Some((match_score + context_score(candidate), candidate))
+        })
+        .collect();
+
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .total_cmp(a_score)
+            .then_with(|| b.latest.start_time.cmp(&a.latest.start_time))
+    });
+
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, candidate)| candidate.latest.into())
+        .collect()
+}
+
+/// Best score over the description, tags and Jira key, or `None` if nothing matched.
+fn match_score(partial: &str, candidate: &Candidate) -> Option<f64> {
+    if partial.is_empty() {
+        return Some(0.0);
+    }
+
+    let mut best = fuzzy_score(partial, &candidate.description);
+
+    let keys = candidate
+        .latest
+        .tags
+        .iter()
+        .chain(candidate.latest.properties.get("jira"));
+    for key in keys {
+        let key = key.trim().to_lowercase();
+        if !key.is_empty() && key.starts_with(partial) {
+            let score = 20.0 + partial.len() as f64 * 4.0;
+            best = Some(best.map_or(score, |b: f64| b.max(score)));
+        }
+    }
+
+    best
+}
+
+/// Frecency and time pattern bonus, kept below what a better text match is worth.
+fn context_score(candidate: &Candidate) -> f64 {
+    let frecency = (1.0 + candidate.frecency).ln() * 6.0;
+    let time_of_day = (1.0 + candidate.same_time_of_day as f64).ln() * 2.0;
+    let weekday = (1.0 + candidate.same_weekday as f64).ln();
+    frecency + time_of_day + weekday
+}
+
+/// Scores `text` containing the characters of `pattern` in order, or `None` if it doesn't.
+fn fuzzy_score(pattern: &str, text: &str) -> Option<f64> {
+    if let Some(index) = text.find(pattern) {
+        let word_start = index == 0 || !text[..index].ends_with(char::is_alphanumeric);
+        let mut score = 30.0 + pattern.len() as f64 * 6.0;
+        if index == 0 {
+            score += 20.0;
+        } else if word_start {
+            score += 10.0;
+        }
+        return Some(score);
+    }
+
+    let mut score = 0.0f64;
+    let mut pattern_chars = pattern.chars().peekable();
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for c in text.chars() {
+        let Some(&p) = pattern_chars.peek() else {
+            break;
+        };
+        if c == p {
+            pattern_chars.next();
+            score += 2.0;
+            if previous_matched {
+                score += 3.0;
+            }
+            if previous.is_none_or(|prev| !prev.is_alphanumeric()) {
+                score += 4.0;
+            }
+            previous_matched = true;
+        } else {
+            score -= 0.2;
+            previous_matched = false;
+        }
+        previous = Some(c);
+    }
+
+    if pattern_chars.peek().is_some() {
+        return None;
+    }
+    Some(score.max(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn entry(description: &str, tags: &[&str], start_time: DateTime<Local>) -> TimeSheetEntry {
+        TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(30)),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("wop", "work on project").is_some());
+        assert!(fuzzy_score("pow", "work on project").is_none());
+        assert!(fuzzy_score("work", "work on project") > fuzzy_score("proj", "work on project"));
+        assert!(fuzzy_score("proj", "work on project") > fuzzy_score("wop", "work on project"));
+    }
+
+    #[test]
+    fn test_rank_suggestions() {
+        let now = Local.with_ymd_and_hms(2025, 6, 10, 9, 0, 0).unwrap();
+        let mut entries = Vec::new();
+        // Frequent daily standup, always around 9
+        for day in 1..20 {
+            entries.push(entry("Standup", &["meeting"], now - Duration::days(day)));
+        }
+        // Used once, but more recently
+        entries.push(entry("Stakeholder sync", &["meeting"], now - Duration::hours(3)));
+        let mut jira = entry("Fix login", &["dev"], now - Duration::days(40));
+        jira.properties.insert("jira".to_string(), "AUTH-42".to_string());
+        entries.push(jira);
+
+        let suggestions = rank_suggestions(&entries, "st", now, 5);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].description, "Standup");
+        assert_eq!(suggestions[1].description, "Stakeholder sync");
+
+        // Fuzzy description match
+        let suggestions = rank_suggestions(&entries, "stkh", now, 5);
+        assert_eq!(suggestions[0].description, "Stakeholder sync");
+
+        // Jira key and tag prefix match
+        let suggestions = rank_suggestions(&entries, "auth-", now, 5);
+        assert_eq!(suggestions[0].description, "Fix login");
+        let suggestions = rank_suggestions(&entries, "meet", now, 5);
+        assert_eq!(suggestions.len(), 2);
+
+        assert_eq!(rank_suggestions(&entries, "", now, 1).len(), 1);
+        assert!(rank_suggestions(&entries, "xyz", now, 5).is_empty());
+    }
+}
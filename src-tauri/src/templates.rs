@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use chrono::{Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::{timesheet_sidecar_path, write_entries, TimeSheetEntry, TimeSheetEntryTemplate};
+
+/// Named template kept across sessions, listed in the order the user arranged them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTemplate {
+    pub name: String,
+    #[serde(flatten)]
+    pub template: TimeSheetEntryTemplate,
+    /// When set, the entry started from the template ends after that long if stopped later.
+    pub default_duration_minutes: Option<u32>,
+}
+
+/// Property holding the default duration of a running entry started from a template, until it's stopped.
+pub const DEFAULT_DURATION_PROPERTY: &str = "default_duration_minutes";
+
+/// Ends the entry being stopped after its default duration if that comes first, dropping the property.
+pub fn apply_default_duration(entry: &mut TimeSheetEntry) {
+    let Some(end_time) = entry.end_time else {
+        return;
+    };
+    let Some(minutes) = entry.properties.remove(DEFAULT_DURATION_PROPERTY) else {
+        return;
+    };
+    if let Ok(minutes) = minutes.parse() {
+        entry.end_time = Some(end_time.min(entry.start_time + Duration::minutes(minutes)));
+    }
+}
+
+/// `TEMPLATES_PATH`, or `<timesheet>.templates.json` next to `TIMESHEET_PATH`.
+fn templates_path() -> PathBuf {
+    match std::env::var("TEMPLATES_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("templates.json"),
+    }
+}
+
+pub fn load_templates() -> Vec<SavedTemplate> {
+    let path = templates_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Vec::new();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Templates file should be valid json")
+}
+
+fn write_templates(templates: &[SavedTemplate]) {
+    let file = std::fs::File::create(templates_path()).unwrap();
+    serde_json::to_writer_pretty(file, templates).unwrap();
+}
+
+#[tauri::command]
+pub fn get_templates() -> Vec<SavedTemplate> {
+    load_templates()
+}
+
+/// Adds the template, or replaces the one with the same name while keeping its position.
+#[tauri::command]
+pub fn save_template(template: SavedTemplate) -> bool {
+    let mut templates = load_templates();
+    match templates.iter_mut().find(|t| t.name == template.name) {
+        Some(existing) => *existing = template,
+        None => templates.push(template),
+    }
+    write_templates(&templates);
+
+    true
+}
+
+#[tauri::command]
+pub fn delete_template(name: String) -> bool {
+    let mut templates = load_templates();
+    let len = templates.len();
+    templates.retain(|t| t.name != name);
+    if templates.len() == len {
+        return false;
+    }
+    write_templates(&templates);
+
+    true
+}
+
+/// Moves the template to `index`, clamped to the end of the list.
+#[tauri::command]
+pub fn move_template(name: String, index: usize) -> bool {
+    let mut templates = load_templates();
+    let Some(position) = templates.iter().position(|t| t.name == name) else {
+        return false;
+    };
+    let template = templates.remove(position);
+    templates.insert(index.min(templates.len()), template);
+    write_templates(&templates);
+
+    true
+}
+
+/// Starts a new entry now from the template, stopping whatever entry was running.
+#[tauri::command]
+pub fn start_from_template(cache: &EntryCache, name: String) -> bool {
+    let Some(saved) = load_templates().into_iter().find(|t| t.name == name) else {
+        eprintln!("[WARN] No template named {name}");
+        return false;
+    };
+
+    let now = Local::now();
+    let mut entries = cache.entries().to_vec();
+    for entry in entries.iter_mut().filter(|e| e.end_time.is_none()) {
+        entry.end_time = Some(now);
+        apply_default_duration(entry);
+    }
+    let mut properties = saved.template.properties;
+    if let Some(minutes) = saved.default_duration_minutes {
+        properties.insert(DEFAULT_DURATION_PROPERTY.to_string(), minutes.to_string());
+    }
+    entries.push(TimeSheetEntry {
+        description: saved.template.description,
+        start_time: now,
+        end_time: None,
+        tags: saved.template.tags,
+        properties,
+    });
+    write_entries(cache, &entries);
+
+    println!("[INFO] Started entry from template {name}");
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_entries, lock_test_env};
+    use std::collections::HashMap;
+
+    fn template(name: &str, default_duration_minutes: Option<u32>) -> SavedTemplate {
+        SavedTemplate {
+            name: name.to_string(),
+            template: TimeSheetEntryTemplate {
+                description: format!("{name} description"),
+                tags: vec!["meeting".to_string()],
+                properties: HashMap::from([("jira".to_string(), "ABC-1".to_string())]),
+            },
+            default_duration_minutes,
+        }
+    }
+
+    #[test]
+    fn test_template_crud_and_ordering() {
+        let _env = lock_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TEMPLATES_PATH");
+
+        assert!(save_template(template("standup", Some(15))));
+        assert!(save_template(template("review", None)));
+        assert!(save_template(template("lunch", Some(60))));
+        assert!(dir.path().join("timesheet.templates.json").exists());
+
+        assert!(move_template("lunch".to_string(), 0));
+        let names: Vec<String> = get_templates().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["lunch", "standup", "review"]);
+
+        // Saving under an existing name replaces in place
+        assert!(save_template(template("standup", Some(10))));
+        let templates = get_templates();
+        assert_eq!(templates.len(), 3);
+        assert_eq!(templates[1].default_duration_minutes, Some(10));
+
+        assert!(delete_template("review".to_string()));
+        assert!(!delete_template("review".to_string()));
+        assert!(!move_template("review".to_string(), 0));
+        assert_eq!(get_templates().len(), 2);
+    }
+
+    #[test]
+    fn test_start_from_template() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TEMPLATES_PATH");
+
+        save_template(template("review", None));
+        save_template(template("standup", Some(15)));
+
+        assert!(start_from_template(&cache, "review".to_string()));
+        assert!(start_from_template(&cache, "standup".to_string()));
+        assert!(!start_from_template(&cache, "missing".to_string()));
+
+        let mut entries = get_entries();
+        assert_eq!(entries.len(), 2);
+        // Starting standup stopped the running review entry
+        assert!(entries[0].end_time.is_some());
+        assert_eq!(entries[1].description, "standup description");
+        assert_eq!(entries[1].tags, vec!["meeting"]);
+        assert_eq!(entries[1].properties.get("jira").unwrap(), "ABC-1");
+        assert_eq!(entries[1].end_time, None, "Nothing ends in the future");
+
+        // Stopped long after its default duration
+        entries[1].start_time -= Duration::hours(1);
+        write_entries(&cache, &entries);
+        assert!(start_from_template(&cache, "review".to_string()));
+        let entries = get_entries();
+        assert_eq!(
+            entries[1].end_time.unwrap() - entries[1].start_time,
+            Duration::minutes(15)
+        );
+        assert!(!entries[1]
+            .properties
+            .contains_key(DEFAULT_DURATION_PROPERTY));
+
+        // Stopped from the app
+        assert!(start_from_template(&cache, "standup".to_string()));
+        let mut standup = get_entries().pop().unwrap();
+        let start_time = standup.start_time;
+        standup.end_time = Some(start_time + Duration::hours(1));
+        assert!(crate::update_entry(
+            &cache,
+            standup.description.clone(),
+            start_time,
+            standup
+        ));
+        assert_eq!(
+            get_entries().pop().unwrap().end_time,
+            Some(start_time + Duration::minutes(15))
+        );
+    }
+}
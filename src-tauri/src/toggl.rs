@@ -0,0 +1,264 @@
+use std::path::Path;
+
+use chrono::Duration;
+use serde::Serialize;
+
+use crate::cache::EntryCache;
+use crate::import::{self, ColumnMapping, ImportSummary};
+use crate::rounding::rounded_durations;
+use crate::{parse_date, write_entries, TimeSheetEntry};
+
+/// One row of Toggl's detailed report csv, as read back with the Toggl import preset.
+#[derive(Debug, Serialize)]
+struct TogglEntryExport<'a> {
+    #[serde(rename = "Description")]
+    description: &'a str,
+    #[serde(rename = "Start date")]
+    start_date: String,
+    #[serde(rename = "Start time")]
+    start_time: String,
+    #[serde(rename = "End date")]
+    end_date: String,
+    #[serde(rename = "End time")]
+    end_time: String,
+    #[serde(rename = "Duration")]
+    duration: String,
+    #[serde(rename = "Tags")]
+    tags: String,
+    #[serde(rename = "Project")]
+    project: &'a str,
+}
+
+/// `HH:MM:SS`, hours going past 24 like in Toggl's reports.
+fn format_duration(seconds: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Parses `HH:MM:SS` durations.
+pub(crate) fn parse_duration(duration: &str) -> Option<Duration> {
+    let mut parts = duration
+        .trim()
+        .splitn(3, ':')
+        .map(|p| p.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(Duration::seconds(hours * 3600 + minutes * 60 + seconds))
+}
+
+fn to_toggl(entry: &TimeSheetEntry, duration: Duration) -> Option<TogglEntryExport<'_>> {
+    entry.end_time?;
+    // Ends where the rounded duration does, Toggl reading the end rather than the duration
+    let end_time = entry.start_time + duration;
+    Some(TogglEntryExport {
+        description: &entry.description,
+        start_date: entry.start_time.format("%Y-%m-%d").to_string(),
+        start_time: entry.start_time.format("%H:%M:%S").to_string(),
+        end_date: end_time.format("%Y-%m-%d").to_string(),
+        end_time: end_time.format("%H:%M:%S").to_string(),
+        duration: format_duration(duration.num_seconds()),
+        // Marker added when reading the Toggl sheet, not a tag of their own
+        tags: entry
+            .tags
+            .iter()
+            .filter(|t| *t != "Toggl")
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", "),
+        project: entry
+            .properties
+            .get("project")
+            .map(String::as_str)
+            .unwrap_or_default(),
+    })
+}
+
+/// Writes the finished entries as a Toggl detailed report, returning how many were written.
+///
+/// Durations are rounded per `ROUNDING_MINUTES`.
+pub fn write_toggl_csv<'a>(
+    path: impl AsRef<Path>,
+    entries: impl IntoIterator<Item = &'a TimeSheetEntry>,
+) -> usize {
+    let mut writer = csv::Writer::from_path(path).unwrap();
+    let mut count = 0;
+    for row in rounded_durations(entries)
+        .into_iter()
+        .filter_map(|(entry, duration)| to_toggl(entry, duration))
+    {
+        writer.serialize(row).unwrap();
+        count += 1;
+    }
+    writer.flush().unwrap();
+
+    count
+}
+
+#[tauri::command]
+pub fn export_toggl(
+    cache: &EntryCache,
+    path: String,
+    from: &str,
+    to: &str,
+) -> Result<usize, String> {
+    let (from, to) = (parse_date(from)?, parse_date(to)?);
+
+    let entries = cache.in_range(from..=to);
+    let count = write_toggl_csv(&path, &entries);
+    println!("[INFO] Exported {count} entries to {path}");
+
+    Ok(count)
+}
+
+/// Reads a Toggl detailed report with [`ColumnMapping::default()`], which maps its project, client,
+/// billable, email and task columns to properties.
+pub fn read_toggl_csv(path: impl AsRef<Path>) -> Result<Vec<TimeSheetEntry>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    import::read_csv(&content, &ColumnMapping::default())?
+        .into_iter()
+        .collect()
+}
+
+/// Reads a Toggl detailed report, tagging its entries so they stay recognizable in the timesheet.
+pub fn read_toggl_sheet(path: impl AsRef<Path>) -> Result<Vec<TimeSheetEntry>, String> {
+    let mut entries = read_toggl_csv(path)?;
+    for entry in entries.iter_mut() {
+        if !entry.tags.iter().any(|t| t == "Toggl") {
+            entry.tags.push("Toggl".to_string());
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Imports a Toggl detailed report into the timesheet once and for all, so `TOGGL_SHEET_PATH` can be dropped.
+#[tauri::command]
+pub fn import_toggl(cache: &EntryCache, path: String) -> Result<ImportSummary, String> {
+    // Tagged like when read from `TOGGL_SHEET_PATH`
+    let imported = read_toggl_sheet(&path)?;
+    let mut entries = cache.entries().to_vec();
+    let report = import::merge(&mut entries, imported);
+    if report.imported > 0 || report.enriched > 0 {
+        write_entries(cache, &entries);
+    }
+
+    println!(
+        "[INFO] Imported {} entries from {path}, skipped {} duplicates",
+        report.imported, report.duplicates
+    );
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock_test_env;
+    use chrono::{Local, TimeZone};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_round_trip() {
+        let start = Local.with_ymd_and_hms(2025, 6, 10, 23, 30, 0).unwrap();
+        let entry = |description: &str, start_time, minutes, tags: &[&str]| TimeSheetEntry {
+            description: description.to_string(),
+            start_time,
+            end_time: Some(start_time + Duration::minutes(minutes)),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            properties: HashMap::from([("project".to_string(), "Internal".to_string())]),
+        };
+        let mut running = entry("Running", start + Duration::hours(2), 0, &["dev"]);
+        running.end_time = None;
+        let entries = vec![
+            entry("Past midnight, \"late\"", start, 90, &["dev", "PROJ"]),
+            entry("Long", start + Duration::hours(1), 25 * 60, &["meeting"]),
+            running,
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("toggl.csv");
+        assert_eq!(write_toggl_csv(&path, &entries), 2);
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "Description,Start date,Start time,End date,End time,Duration,Tags,Project"
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(",01:30:00,\"dev, PROJ\",Internal"));
+        assert!(lines.next().unwrap().contains(",25:00:00,"));
+
+        let imported = read_toggl_csv(&path).unwrap();
+        assert_eq!(imported.len(), 2);
+        for (imported, exported) in imported.iter().zip(entries.iter()) {
+            assert_eq!(imported.description, exported.description);
+            assert_eq!(imported.start_time, exported.start_time);
+            assert_eq!(imported.end_time, exported.end_time);
+            assert_eq!(imported.tags, exported.tags);
+        }
+    }
+
+    #[test]
+    fn test_import_maps_columns_and_deduplicates() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TOGGL_SHEET_PATH");
+
+        let path = dir.path().join("toggl.csv");
+        std::fs::write(
+            &path,
+            "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()\n\
+             Ann,ann@example.com,Acme,Website,Design,Mockups,Yes,2025-06-10,09:00:00,2025-06-10,10:30:00,01:30:00,\"design, web\",\n\
+             Ann,ann@example.com,,,,Standup,No,2025-06-10,11:00:00,,,00:15:00,,\n\
+             Ann,ann@example.com,Acme,Website,Design,Mockups,Yes,2025-06-10,09:00:00,2025-06-10,10:30:00,01:30:00,\"design, web\",\n\
+             Ann,ann@example.com,Acme,Intranet,,Already there,No,2025-06-09,14:00:00,2025-06-09,15:00:00,01:00:00,,\n",
+        )
+        .unwrap();
+
+        let entries = read_toggl_csv(&path).unwrap();
+        assert_eq!(entries[0].tags, vec!["design", "web"]);
+        assert_eq!(entries[0].properties["project"], "Website");
+        assert_eq!(entries[0].properties["client"], "Acme");
+        assert_eq!(entries[0].properties["task"], "Design");
+        assert_eq!(entries[0].properties["billable"], "true");
+        assert_eq!(entries[0].properties["email"], "ann@example.com");
+        assert_eq!(
+            entries[1].end_time,
+            Some(entries[1].start_time + Duration::minutes(15))
+        );
+        assert!(!entries[1].properties.contains_key("project"));
+
+        let mut existing = entries[3].clone();
+        existing.properties.clear();
+        write_entries(&cache, &[existing]);
+
+        let report = import_toggl(&cache, path.to_string_lossy().to_string()).unwrap();
+        assert_eq!(
+            report,
+            ImportSummary {
+                imported: 2,
+                duplicates: 2,
+                enriched: 1
+            }
+        );
+        let entries = crate::get_entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].properties["project"], "Intranet");
+        assert!(entries[1].tags.contains(&"Toggl".to_string()));
+
+        let report = import_toggl(&cache, path.to_string_lossy().to_string()).unwrap();
+        assert_eq!(
+            report.imported + report.enriched,
+            0,
+            "Importing twice changes nothing"
+        );
+    }
+}
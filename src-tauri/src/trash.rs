@@ -0,0 +1,184 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::EntryCache;
+use crate::{journal, timesheet_sidecar_path, write_entries, TimeSheetEntry};
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Entry removed through `delete_entry`, kept until restored or purged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedEntry {
+    pub entry: TimeSheetEntry,
+    /// Epoch milliseconds
+    pub deleted_at: i64,
+}
+
+/// `TRASH_PATH`, or `<timesheet>.trash.json` next to `TIMESHEET_PATH`.
+fn trash_path() -> PathBuf {
+    match std::env::var("TRASH_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => timesheet_sidecar_path("trash.json"),
+    }
+}
+
+/// How long trashed entries are kept, from `TRASH_RETENTION_DAYS`.
+fn retention() -> Duration {
+    let days = std::env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .map(|days| days.parse().expect("TRASH_RETENTION_DAYS should be a number of days"))
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    Duration::days(days)
+}
+
+fn load_trash() -> Vec<TrashedEntry> {
+    let path = trash_path();
+    if !std::fs::exists(&path).unwrap() {
+        return Vec::new();
+    }
+
+    let file = std::fs::File::open(&path).unwrap();
+    serde_json::from_reader(file).expect("Trash file should be valid json")
+}
+
+fn write_trash(trash: &[TrashedEntry]) {
+    let file = std::fs::File::create(trash_path()).unwrap();
+    serde_json::to_writer(file, trash).unwrap();
+}
+
+fn is_same_entry(a: &TimeSheetEntry, b: &TimeSheetEntry) -> bool {
+    a.description == b.description && a.start_time == b.start_time
+}
+
+/// Moves deleted entries to the trash, purging expired ones on the way.
+pub fn put(entries: Vec<TimeSheetEntry>) {
+    let deleted_at = Local::now().timestamp_millis();
+    let mut trash = load_trash();
+    trash.extend(entries.into_iter().map(|entry| TrashedEntry { entry, deleted_at }));
+    write_trash(&purged(trash, Local::now()));
+}
+
+/// Drops the entry from the trash, if there, once it is back in the timesheet.
+pub fn forget(entry: &TimeSheetEntry) {
+    let mut trash = load_trash();
+    let len = trash.len();
+    trash.retain(|t| !is_same_entry(&t.entry, entry));
+    if trash.len() != len {
+        write_trash(&trash);
+    }
+}
+
+fn purged(mut trash: Vec<TrashedEntry>, now: DateTime<Local>) -> Vec<TrashedEntry> {
+    let oldest = (now - retention()).timestamp_millis();
+    trash.retain(|t| t.deleted_at >= oldest);
+    trash
+}
+
+/// Permanently removes entries trashed longer ago than the retention period.
+#[tauri::command]
+pub fn purge_trash() -> usize {
+    let trash = load_trash();
+    let len = trash.len();
+    let trash = purged(trash, Local::now());
+    if trash.len() != len {
+        write_trash(&trash);
+        println!("[INFO] Purged {} entries from trash", len - trash.len());
+    }
+
+    len - trash.len()
+}
+
+/// Trashed entries, most recently deleted first.
+#[tauri::command]
+pub fn get_trash() -> Vec<TrashedEntry> {
+    let mut trash = load_trash();
+    trash.reverse();
+    trash
+}
+
+#[tauri::command]
+pub fn restore_entry(cache: &EntryCache, description: String, start_time: DateTime<Local>) -> bool {
+    let mut trash = load_trash();
+    let Some(position) = trash
+        .iter()
+        .position(|t| t.entry.description == description && t.entry.start_time == start_time)
+    else {
+        return false;
+    };
+    let restored = trash.remove(position).entry;
+
+    let mut entries = cache.entries().to_vec();
+    entries.push(restored.clone());
+    write_entries(cache, &entries);
+    write_trash(&trash);
+    journal::record(journal::Operation::Add { entry: restored });
+
+    println!("[INFO] Entry restored from trash");
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_entry, delete_entry, get_entries, lock_test_env};
+    use chrono::SubsecRound;
+
+    #[test]
+    fn test_delete_and_restore() {
+        let _env = lock_test_env();
+        let cache = EntryCache::new();
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("TIMESHEET_PATH", dir.path().join("timesheet.csv"));
+        std::env::remove_var("TRASH_PATH");
+        std::env::remove_var("TRASH_RETENTION_DAYS");
+
+        // Stored with millisecond precision
+        let now = Local::now().trunc_subsecs(3);
+        for (i, description) in ["Kept", "Deleted"].into_iter().enumerate() {
+            assert!(add_entry(&cache, TimeSheetEntry {
+                description: description.to_string(),
+                start_time: now + Duration::milliseconds(i as i64),
+                end_time: None,
+                tags: vec!["test".to_string()],
+                properties: Default::default(),
+            }));
+        }
+
+        assert!(delete_entry(&cache, "Deleted".to_string(), now + Duration::milliseconds(1)));
+        assert_eq!(get_entries().len(), 1);
+        let trash = get_trash();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].entry.description, "Deleted");
+
+        assert!(!restore_entry(&cache, "Deleted".to_string(), now));
+        assert!(restore_entry(&cache, "Deleted".to_string(), now + Duration::milliseconds(1)));
+        assert_eq!(get_entries().len(), 2);
+        assert!(get_trash().is_empty());
+    }
+
+    #[test]
+    fn test_purge_after_retention() {
+        let now = Local::now();
+        let trashed = |days_ago: i64| TrashedEntry {
+            entry: TimeSheetEntry {
+                description: format!("{days_ago} days ago"),
+                start_time: now,
+                end_time: None,
+                tags: vec![],
+                properties: Default::default(),
+            },
+            deleted_at: (now - Duration::days(days_ago)).timestamp_millis(),
+        };
+
+        let _env = lock_test_env();
+        std::env::set_var("TRASH_RETENTION_DAYS", "7");
+        let trash = purged(vec![trashed(10), trashed(6), trashed(0)], now);
+        std::env::remove_var("TRASH_RETENTION_DAYS");
+
+        let descriptions: Vec<String> = trash.into_iter().map(|t| t.entry.description).collect();
+        assert_eq!(descriptions, vec!["6 days ago", "0 days ago"]);
+    }
+}